@@ -45,7 +45,8 @@ fn bench_full(b: &mut Bencher) {
 
         let prefix = parsed.prefix();
         assert!(prefix.is_some());
-        let &(name, user, host) = prefix.unwrap();
+        let prefix = prefix.unwrap();
+        let (name, user, host) = (prefix.name(), prefix.user(), prefix.host());
 
         assert_eq!(name, "name");
         assert_eq!(user, Some("user"));
@@ -147,3 +148,43 @@ fn bench_params_iter(b: &mut Bencher) {
         }
     });
 }
+
+const MULTI_ACCESS: &str =
+    "@key1=value1;key2=value2 :name!user@host PRIVMSG #channel :Hello World!";
+
+#[bench]
+fn bench_multi_access_message(b: &mut Bencher) {
+    let message = Message::from(MULTI_ACCESS);
+
+    b.iter(|| {
+        assert_eq!(message.command().unwrap(), "PRIVMSG");
+        assert_eq!(message.prefix().unwrap().unwrap().name(), "name");
+        assert_eq!(message.params().unwrap().next(), Some("#channel"));
+        assert_eq!(message.trailing().unwrap(), Some("Hello World!"));
+    });
+}
+
+#[bench]
+fn bench_multi_access_indexed(b: &mut Bencher) {
+    let message = Message::from(MULTI_ACCESS).indexed().unwrap();
+
+    b.iter(|| {
+        assert_eq!(message.command(), "PRIVMSG");
+        assert_eq!(message.prefix().unwrap().name(), "name");
+        assert_eq!(message.param(0), Some("#channel"));
+        assert_eq!(message.trailing(), Some("Hello World!"));
+    });
+}
+
+#[bench]
+fn bench_multi_access_indexed_with_indexing(b: &mut Bencher) {
+    let str = String::from(MULTI_ACCESS);
+
+    b.iter(|| {
+        let message = Message::from(str.as_str()).indexed().unwrap();
+        assert_eq!(message.command(), "PRIVMSG");
+        assert_eq!(message.prefix().unwrap().name(), "name");
+        assert_eq!(message.param(0), Some("#channel"));
+        assert_eq!(message.trailing(), Some("Hello World!"));
+    });
+}
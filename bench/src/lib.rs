@@ -1,4 +1,4 @@
-#![feature(test)]
+#![cfg_attr(test, feature(test))]
 #![allow(clippy::cargo)]
 #![allow(clippy::all)]
 
@@ -1,7 +1,15 @@
-use crate::errors::ParserError;
+use crate::charset;
+use crate::charset::Charset;
+use crate::ctcp;
+use crate::ctcp::Kind;
+use crate::errors::{BuildError, Component, ParserError, ParserErrorKind};
 use crate::parsed::Parsed;
+use crate::prefix::{OwnedPrefix, Prefix};
+use crate::raw::RawMessage;
+use crate::tags;
+use crate::tags::Tags;
+use crate::tokenizer::Tokenizer;
 use crate::Message;
-use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::str::FromStr;
 
@@ -60,10 +68,9 @@ use std::str::FromStr;
 /// ```
 #[derive(Debug, Clone, Eq, PartialEq, Default)]
 pub struct Builder {
-    tags: HashMap<String, String>,
-    prefix_name: Option<String>,
-    prefix_user: Option<String>,
-    prefix_host: Option<String>,
+    /// Escaped tag values. Tags without value are `None`.
+    tags: Tags<String, Option<String>>,
+    prefix: Option<OwnedPrefix>,
     command: String,
     params: Vec<String>,
     trailing: Option<String>,
@@ -83,44 +90,136 @@ impl Builder {
     /// # }
     /// ```
     pub fn new<S: ToString>(command: S) -> Self {
+        Builder::try_new(command).unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Creates a builder with the given command.
+    ///
+    /// Fails if **command** is invalid, see [Builder::try_command].
+    pub fn try_new<S: ToString>(command: S) -> Result<Self, BuildError> {
         Builder {
-            tags: HashMap::new(),
-            prefix_name: None,
-            prefix_user: None,
-            prefix_host: None,
+            tags: Tags::new(),
+            prefix: None,
             command: "".to_string(),
             params: Vec::new(),
             trailing: None,
         }
-        .command(command.to_string())
+        .try_command(command)
+    }
+
+    /// Creates a builder for a CTCP request sent as `PRIVMSG` to **target**.
+    ///
+    /// The params are quoted so they may contain any character.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::builder::Builder;
+    ///
+    /// let message = Builder::ctcp_request("#channel", "ACTION", Some("waves")).build();
+    /// assert_eq!("PRIVMSG #channel :\x01ACTION waves\x01", message.to_string());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if **target** isn't a valid param. See [Builder::try_ctcp_request] for a
+    /// non-panicking variant.
+    pub fn ctcp_request<S: ToString>(target: S, command: &str, params: Option<&str>) -> Self {
+        Builder::try_ctcp_request(target, command, params).unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Creates a builder for a CTCP request sent as `PRIVMSG` to **target**.
+    ///
+    /// Fails if **target** isn't a valid param, see [Builder::try_param].
+    pub fn try_ctcp_request<S: ToString>(
+        target: S,
+        command: &str,
+        params: Option<&str>,
+    ) -> Result<Self, BuildError> {
+        Builder::ctcp(Kind::Request, target, command, params)
+    }
+
+    /// Creates a builder for a CTCP reply sent as `NOTICE` to **target**.
+    ///
+    /// The params are quoted so they may contain any character.
+    ///
+    /// # Panics
+    ///
+    /// Panics if **target** isn't a valid param. See [Builder::try_ctcp_reply] for a
+    /// non-panicking variant.
+    pub fn ctcp_reply<S: ToString>(target: S, command: &str, params: Option<&str>) -> Self {
+        Builder::try_ctcp_reply(target, command, params).unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Creates a builder for a CTCP reply sent as `NOTICE` to **target**.
+    ///
+    /// Fails if **target** isn't a valid param, see [Builder::try_param].
+    pub fn try_ctcp_reply<S: ToString>(
+        target: S,
+        command: &str,
+        params: Option<&str>,
+    ) -> Result<Self, BuildError> {
+        Builder::ctcp(Kind::Reply, target, command, params)
+    }
+
+    fn ctcp<S: ToString>(
+        kind: Kind,
+        target: S,
+        command: &str,
+        params: Option<&str>,
+    ) -> Result<Self, BuildError> {
+        Builder::new(kind.command())
+            .try_param(target)?
+            .try_trailing(ctcp::encode(command, params))
     }
 
     /// Set the command.
     ///
     /// # Panics
     ///
-    /// Panics if **cmd** is empty.
-    pub fn command<S: ToString>(mut self, cmd: S) -> Builder {
+    /// Panics if **cmd** is invalid. See [Builder::try_command] for a non-panicking variant.
+    pub fn command<S: ToString>(self, cmd: S) -> Builder {
+        self.try_command(cmd)
+            .unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Set the command.
+    ///
+    /// Fails if **cmd** is empty or contains spaces, CR, LF or NUL.
+    pub fn try_command<S: ToString>(mut self, cmd: S) -> Result<Builder, BuildError> {
         let cmd = cmd.to_string();
-        if cmd.is_empty() {
-            panic!("tried to set empty command");
-        }
+        validate_command(&cmd)?;
         self.command = cmd;
-        self
+        Ok(self)
     }
 
-    /// Set a tag.
+    /// Set a tag. The value is escaped when building the message, so it is passed unescaped.
+    ///
+    /// Tags are written in the order they were first set. Setting a tag again replaces its
+    /// value but keeps its position.
     ///
     /// # Panics
     ///
-    /// Panics if **key** is empty. **value** is allowed to be empty.
-    pub fn tag<SK: ToString, SV: ToString>(mut self, key: SK, value: SV) -> Builder {
+    /// Panics if **key** is invalid. **value** is allowed to be empty. See [Builder::try_tag]
+    /// for a non-panicking variant.
+    pub fn tag<SK: ToString, SV: ToString>(self, key: SK, value: SV) -> Builder {
+        self.try_tag(key, value)
+            .unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Set a tag. The value is escaped when building the message, so it is passed unescaped.
+    ///
+    /// Fails if **key** is empty or contains `=`, `;`, spaces, CR, LF or NUL.
+    pub fn try_tag<SK: ToString, SV: ToString>(
+        mut self,
+        key: SK,
+        value: SV,
+    ) -> Result<Builder, BuildError> {
         let key = key.to_string();
-        if key.is_empty() {
-            panic!("tried to set tag with empty key");
-        }
-        self.tags.insert(key, value.to_string());
-        self
+        validate_tag_key(&key)?;
+        self.tags
+            .insert(key, Some(tags::escape(&value.to_string()).into_owned()));
+        Ok(self)
     }
 
     /// Set a prefix name.
@@ -128,45 +227,78 @@ impl Builder {
     /// # Panics
     ///
     /// Panics if **name** is empty, **user or host** == **Some("")** or **user** is some and **host** is none.
-    pub fn prefix<SN, SU, SH>(mut self, name: SN, user: Option<SU>, host: Option<SH>) -> Builder
+    /// See [Builder::try_prefix] for a non-panicking variant.
+    pub fn prefix<SN, SU, SH>(self, name: SN, user: Option<SU>, host: Option<SH>) -> Builder
     where
         SN: ToString,
         SU: ToString,
         SH: ToString,
     {
-        let name = name.to_string();
-        if name.is_empty() {
-            panic!("tried to set empty prefix name");
-        }
-        let user = user.map(|user| user.to_string());
-        if user.is_some() && user.as_ref().unwrap().is_empty() {
-            panic!("tried to set empty prefix user");
-        }
-        let host = host.map(|host| host.to_string());
-        if host.is_some() && host.as_ref().unwrap().is_empty() {
-            panic!("tried to set empty prefix host");
-        }
-        if user.is_some() && host.is_none() {
-            panic!("tried to set prefix user without host");
-        }
-        self.prefix_name = Some(name);
-        self.prefix_user = user;
-        self.prefix_host = host;
-        self
+        self.try_prefix(name, user, host)
+            .unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Set a prefix name.
+    ///
+    /// Fails if **name** is empty, **user or host** == **Some("")**, **user** is some and **host**
+    /// is none or any part contains spaces, CR, LF, NUL or a separator of the prefix.
+    pub fn try_prefix<SN, SU, SH>(
+        self,
+        name: SN,
+        user: Option<SU>,
+        host: Option<SH>,
+    ) -> Result<Builder, BuildError>
+    where
+        SN: ToString,
+        SU: ToString,
+        SH: ToString,
+    {
+        self.try_with_prefix(OwnedPrefix::new(name, user, host))
+    }
+
+    /// Set the prefix from a [Prefix] or [OwnedPrefix].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix is invalid. See [Builder::try_with_prefix] for a non-panicking
+    /// variant.
+    pub fn with_prefix<P: Into<OwnedPrefix>>(self, prefix: P) -> Builder {
+        self.try_with_prefix(prefix)
+            .unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Set the prefix from a [Prefix] or [OwnedPrefix].
+    ///
+    /// Fails for the same reasons as [Builder::try_prefix].
+    pub fn try_with_prefix<P: Into<OwnedPrefix>>(
+        mut self,
+        prefix: P,
+    ) -> Result<Builder, BuildError> {
+        let prefix = prefix.into();
+        validate_prefix(&prefix.as_prefix())?;
+        self.prefix = Some(prefix);
+        Ok(self)
     }
 
     /// Add a param.
     ///
     /// # Panics
     ///
-    /// Panics if **param** is empty.
-    pub fn param<S: ToString>(mut self, param: S) -> Builder {
+    /// Panics if **param** is invalid. See [Builder::try_param] for a non-panicking variant.
+    pub fn param<S: ToString>(self, param: S) -> Builder {
+        self.try_param(param)
+            .unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Add a param.
+    ///
+    /// Fails if **param** is empty, starts with ':' or contains spaces, CR, LF or NUL.
+    /// Use [Builder::trailing] for such values.
+    pub fn try_param<S: ToString>(mut self, param: S) -> Result<Builder, BuildError> {
         let param = param.to_string();
-        if param.is_empty() {
-            panic!("tried to add empty param");
-        }
+        validate_param(&param)?;
         self.params.push(param);
-        self
+        Ok(self)
     }
 
     /// Set a param at the given index. If the index is below 0, it won't be set.
@@ -175,18 +307,28 @@ impl Builder {
     ///
     /// # Panics
     ///
-    /// Panics if **param** is empty.
-    pub fn set_param<S: ToString>(mut self, index: usize, param: S) -> Builder {
+    /// Panics if **param** is invalid. See [Builder::try_set_param] for a non-panicking variant.
+    pub fn set_param<S: ToString>(self, index: usize, param: S) -> Builder {
+        self.try_set_param(index, param)
+            .unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Set a param at the given index. Behaves like [Builder::set_param].
+    ///
+    /// Fails if **param** is empty, starts with ':' or contains spaces, CR, LF or NUL.
+    pub fn try_set_param<S: ToString>(
+        mut self,
+        index: usize,
+        param: S,
+    ) -> Result<Builder, BuildError> {
         let param = param.to_string();
-        if param.is_empty() {
-            panic!("tried to set empty param");
-        }
+        validate_param(&param)?;
         if index >= self.params.len() {
             self.params.push(param);
         } else {
             self.params[index] = param;
         }
-        self
+        Ok(self)
     }
 
     pub fn remove_param(mut self, index: usize) -> Builder {
@@ -196,12 +338,82 @@ impl Builder {
         self
     }
 
-    //( Add a trailing param;
+    /// Add a trailing param.
     pub fn trailing<S: ToString>(mut self, trailing: S) -> Builder {
         self.trailing = Some(trailing.to_string());
         self
     }
 
+    /// Add a trailing param.
+    ///
+    /// Fails if **trailing** contains CR, LF or NUL.
+    pub fn try_trailing<S: ToString>(mut self, trailing: S) -> Result<Builder, BuildError> {
+        let trailing = trailing.to_string();
+        validate_trailing(&trailing)?;
+        self.trailing = Some(trailing);
+        Ok(self)
+    }
+
+    /// Validates all parts and creates a Message instance if valid.
+    ///
+    /// In addition to the checks of the `try_*` methods this checks the trailing
+    /// param set by [Builder::trailing] and that at most 15 params are present.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::builder::Builder;
+    /// use irc_rust::errors::BuildError;
+    ///
+    /// let result = Builder::new("PRIVMSG")
+    ///     .param("#channel")
+    ///     .trailing("line\r\nbreak")
+    ///     .try_build();
+    /// assert_eq!(
+    ///     Err(BuildError::InvalidCharacter { part: "trailing", character: '\r' }),
+    ///     result
+    /// );
+    /// ```
+    pub fn try_build(self) -> Result<crate::message::Message, BuildError> {
+        validate_command(&self.command)?;
+        for key in self.tags.keys() {
+            validate_tag_key(key)?;
+        }
+        if let Some(prefix) = &self.prefix {
+            validate_prefix(&prefix.as_prefix())?;
+        }
+        for param in &self.params {
+            validate_param(param)?;
+        }
+        if let Some(trailing) = &self.trailing {
+            validate_trailing(trailing)?;
+        }
+        let count = self.params.len() + self.trailing.iter().count();
+        if count > MAX_PARAMS {
+            return Err(BuildError::TooManyParams(count));
+        }
+        Ok(self.build())
+    }
+
+    /// Create a Message and encode it in the given charset. Characters which can't be
+    /// represented are replaced by `?`. Tags are always encoded as UTF-8.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::charset::Charset;
+    /// use irc_rust::Message;
+    ///
+    /// let message = Message::builder("PRIVMSG")
+    ///     .param("#channel")
+    ///     .trailing("Grüße €")
+    ///     .build_encoded(Charset::Latin1);
+    /// assert_eq!(&b"PRIVMSG #channel :Gr\xfc\xdfe ?"[..], message.as_bytes());
+    /// ```
+    pub fn build_encoded(self, charset: Charset) -> RawMessage {
+        RawMessage::from(charset::encode_line(&self.build().to_string(), charset))
+    }
+
     /// Create a Message instance and return if valid.
     pub fn build(self) -> crate::message::Message {
         let mut str = String::new();
@@ -209,26 +421,21 @@ impl Builder {
             str.push('@');
             for (key, val) in self.tags {
                 str.push_str(key.as_str());
-                str.push('=');
-                str.push_str(val.as_str());
+                if let Some(val) = val {
+                    str.push('=');
+                    str.push_str(val.as_str());
+                }
                 str.push(';')
             }
             str.pop();
             str.push(' ');
         }
-        if let Some(prefix_name) = self.prefix_name {
-            str.push(':');
-            str.push_str(prefix_name.as_str());
+        if let Some(prefix) = self.prefix {
+            let prefix = prefix.as_prefix();
             // Asserting as checked in setters.
-            assert!(self.prefix_user.is_none() || self.prefix_host.is_some());
-            if let Some(user) = self.prefix_user {
-                str.push('!');
-                str.push_str(user.as_str());
-            }
-            if let Some(host) = self.prefix_host {
-                str.push('@');
-                str.push_str(host.as_str());
-            }
+            assert!(prefix.user().is_none() || prefix.host().is_some());
+            str.push(':');
+            str.push_str(&prefix.to_string());
             str.push(' ')
         }
         str.push_str(self.command.as_str());
@@ -242,6 +449,101 @@ impl Builder {
         }
         crate::message::Message::from(str)
     }
+
+    /// Brings the builder into the canonical form of [Message::normalize].
+    pub(crate) fn normalize(mut self) -> Builder {
+        // Values are compared unescaped and empty values are equal to missing values
+        self.tags = self
+            .tags
+            .into_iter()
+            .map(|(key, value)| {
+                let value = value.map(|value| tags::escape(&tags::unescape(&value)).into_owned());
+                (key, value.filter(|value| !value.is_empty()))
+            })
+            .collect();
+        self.tags.sort();
+        self.command = self.command.to_ascii_uppercase();
+        let redundant = self.trailing.as_deref().is_some_and(|trailing| {
+            !trailing.is_empty() && !trailing.starts_with(':') && !trailing.contains(' ')
+        });
+        if redundant {
+            self.params.extend(self.trailing.take());
+        }
+        self
+    }
+}
+
+/// Maximum number of params including the trailing param.
+const MAX_PARAMS: usize = 15;
+
+/// Characters which can never be part of a message outside of tag values.
+const ALWAYS_INVALID: &[char] = &['\r', '\n', '\0'];
+
+fn check_characters(part: &'static str, value: &str, invalid: &[char]) -> Result<(), BuildError> {
+    match value
+        .chars()
+        .find(|ch| ALWAYS_INVALID.contains(ch) || invalid.contains(ch))
+    {
+        Some(character) => Err(BuildError::InvalidCharacter { part, character }),
+        None => Ok(()),
+    }
+}
+
+fn validate_command(command: &str) -> Result<(), BuildError> {
+    if command.is_empty() {
+        return Err(BuildError::EmptyCommand);
+    }
+    check_characters("command", command, &[' '])
+}
+
+fn validate_tag_key(key: &str) -> Result<(), BuildError> {
+    if key.is_empty() {
+        return Err(BuildError::EmptyTagKey);
+    }
+    check_characters("tag key", key, &['=', ';', ' '])
+}
+
+pub(crate) fn validate_prefix(prefix: &Prefix<'_>) -> Result<(), BuildError> {
+    let (name, user, host) = (prefix.name(), prefix.user(), prefix.host());
+    if name.is_empty() {
+        return Err(BuildError::EmptyPrefixName);
+    }
+    check_characters("prefix name", name, &[' ', '!', '@'])?;
+    if let Some(user) = user {
+        if user.is_empty() {
+            return Err(BuildError::EmptyPrefixUser);
+        }
+        check_characters("prefix user", user, &[' ', '@'])?;
+    }
+    if let Some(host) = host {
+        if host.is_empty() {
+            return Err(BuildError::EmptyPrefixHost);
+        }
+        check_characters("prefix host", host, &[' '])?;
+    }
+    if user.is_some() && host.is_none() {
+        return Err(BuildError::PrefixUserWithoutHost);
+    }
+    Ok(())
+}
+
+fn validate_param(param: &str) -> Result<(), BuildError> {
+    if param.is_empty() {
+        return Err(BuildError::EmptyParam);
+    }
+    if param.starts_with(':') {
+        return Err(BuildError::ParamStartsWithColon);
+    }
+    check_characters("param", param, &[' '])
+}
+
+/// Returns true if the param can only be sent as the trailing param.
+pub(crate) fn needs_trailing(param: &str) -> bool {
+    param.is_empty() || param.starts_with(':') || param.contains(' ')
+}
+
+fn validate_trailing(trailing: &str) -> Result<(), BuildError> {
+    check_characters("trailing", trailing, &[])
 }
 
 impl FromStr for Builder {
@@ -250,25 +552,103 @@ impl FromStr for Builder {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parsed = Parsed::try_from(s)?;
 
-        let mut builder = Builder::new(parsed.command().ok_or(ParserError::NoCommand)?);
-        for (key, value) in parsed.tags() {
-            builder = builder.tag(key, value)
+        let no_command = ParserError::new(ParserErrorKind::NoCommand, Component::Command, 0, 0);
+        let command = parsed.command().ok_or(no_command)?;
+        let mut builder = Builder::try_new(command)
+            .map_err(|why| parser_error(s, command, Component::Command, why))?;
+        // Parsed doesn't distinguish tags without value from empty values
+        for res in Tokenizer::new(s)?.tags().as_optional_iter() {
+            let (key, value) = res?;
+            validate_tag_key(key).map_err(|why| parser_error(s, key, Component::Tags, why))?;
+            // Values are kept escaped to reproduce the message byte-exact
+            builder
+                .tags
+                .insert(key.to_string(), value.map(str::to_string));
         }
-        if let Some(&(name, user, host)) = parsed.prefix() {
-            builder = builder.prefix(name, user, host);
+        if let Some(prefix) = parsed.prefix() {
+            builder = builder
+                .try_with_prefix(*prefix)
+                .map_err(|why| prefix_error(s, prefix, why))?;
         }
-        // Flatten to remove empty params
-        for param in parsed.params().flatten() {
-            builder = builder.param(param);
+        // Flatten and filter to remove empty params of repeated spaces
+        for param in parsed.params().flatten().filter(|param| !param.is_empty()) {
+            builder = builder
+                .try_param(param)
+                .map_err(|why| parser_error(s, param, Component::Params, why))?;
         }
         if let Some(trailing) = parsed.trailing() {
-            builder = builder.trailing(trailing);
+            builder = builder
+                .try_trailing(trailing)
+                .map_err(|why| parser_error(s, trailing, Component::Params, why))?;
         }
 
         Ok(builder)
     }
 }
 
+/// Maps the error of a **prefix** of the **message** to a [ParserError] pointing at the
+/// invalid part of the prefix.
+pub(crate) fn prefix_error(message: &str, prefix: &Prefix<'_>, why: BuildError) -> ParserError {
+    let part = match why {
+        BuildError::EmptyPrefixUser
+        | BuildError::PrefixUserWithoutHost
+        | BuildError::InvalidCharacter {
+            part: "prefix user",
+            ..
+        } => prefix.user(),
+        BuildError::EmptyPrefixHost
+        | BuildError::InvalidCharacter {
+            part: "prefix host",
+            ..
+        } => prefix.host(),
+        _ => None,
+    };
+    let part = part.unwrap_or_else(|| prefix.name());
+    parser_error(message, part, Component::Prefix, why)
+}
+
+/// Maps the error of a **part** of the **message** to a [ParserError] pointing at the part or
+/// the invalid character in it.
+fn parser_error(message: &str, part: &str, component: Component, why: BuildError) -> ParserError {
+    let offset = part.as_ptr() as usize - message.as_ptr() as usize;
+    if let BuildError::InvalidCharacter { character, .. } = why {
+        if ALWAYS_INVALID.contains(&character) {
+            let index = part.find(character).unwrap_or_default();
+            return ParserError::new(
+                ParserErrorKind::InvalidCharacter,
+                component,
+                offset + index,
+                character.len_utf8(),
+            );
+        }
+    }
+    let kind = match (component, why) {
+        (Component::Tags, BuildError::EmptyTagKey) => ParserErrorKind::EmptyTagKey,
+        (Component::Tags, _) => ParserErrorKind::InvalidTagKey,
+        (Component::Prefix, BuildError::EmptyPrefixUser)
+        | (Component::Prefix, BuildError::PrefixUserWithoutHost)
+        | (
+            Component::Prefix,
+            BuildError::InvalidCharacter {
+                part: "prefix user",
+                ..
+            },
+        ) => ParserErrorKind::InvalidPrefixUser,
+        (Component::Prefix, BuildError::EmptyPrefixHost)
+        | (
+            Component::Prefix,
+            BuildError::InvalidCharacter {
+                part: "prefix host",
+                ..
+            },
+        ) => ParserErrorKind::InvalidPrefixHost,
+        (Component::Prefix, _) => ParserErrorKind::InvalidPrefixName,
+        (Component::Command, _) => ParserErrorKind::InvalidCommand,
+        _ => ParserErrorKind::EmptyParam,
+    };
+    ParserError::new(kind, component, offset, part.len())
+}
+
 impl TryFrom<Message> for Builder {
     type Error = ParserError;
 
@@ -276,3 +656,149 @@ impl TryFrom<Message> for Builder {
         value.to_builder()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::Builder;
+    use crate::errors::{BuildError, Component, ParserError, ParserErrorKind};
+    use crate::Message;
+
+    #[test]
+    fn test_try_setters() {
+        assert_eq!(
+            Err(BuildError::EmptyCommand),
+            Builder::new("CMD").try_command("")
+        );
+        assert_eq!(
+            Err(BuildError::InvalidCharacter {
+                part: "command",
+                character: ' '
+            }),
+            Builder::new("CMD").try_command("PRIV MSG")
+        );
+        assert_eq!(
+            Err(BuildError::EmptyTagKey),
+            Builder::new("CMD").try_tag("", "value")
+        );
+        assert_eq!(
+            Err(BuildError::InvalidCharacter {
+                part: "tag key",
+                character: '='
+            }),
+            Builder::new("CMD").try_tag("key=", "value")
+        );
+        assert_eq!(
+            Err(BuildError::PrefixUserWithoutHost),
+            Builder::new("CMD").try_prefix("name", Some("user"), None::<&str>)
+        );
+        assert_eq!(
+            Err(BuildError::InvalidCharacter {
+                part: "prefix name",
+                character: '!'
+            }),
+            Builder::new("CMD").try_prefix("na!me", None::<&str>, None::<&str>)
+        );
+        assert_eq!(
+            Err(BuildError::EmptyParam),
+            Builder::new("CMD").try_param("")
+        );
+        assert_eq!(
+            Err(BuildError::ParamStartsWithColon),
+            Builder::new("CMD").try_param(":param")
+        );
+        for (param, character) in &[("a b", ' '), ("a\rb", '\r'), ("a\nb", '\n'), ("a\0b", '\0')] {
+            assert_eq!(
+                Err(BuildError::InvalidCharacter {
+                    part: "param",
+                    character: *character
+                }),
+                Builder::new("CMD").try_set_param(0, param)
+            );
+        }
+        assert_eq!(
+            Err(BuildError::InvalidCharacter {
+                part: "trailing",
+                character: '\n'
+            }),
+            Builder::new("CMD").try_trailing("a\nb")
+        );
+    }
+
+    #[test]
+    fn test_try_build() -> Result<(), BuildError> {
+        let message = Builder::new("PRIVMSG")
+            .try_tag("key", "a value; with specials")?
+            .try_prefix("name", Some("user"), Some("host"))?
+            .try_param("#channel")?
+            .try_trailing(":a trailing with spaces")?
+            .try_build()?;
+        assert_eq!(
+            "@key=a\\svalue\\:\\swith\\sspecials :name!user@host PRIVMSG #channel ::a trailing with spaces",
+            message.to_string()
+        );
+
+        let mut builder = Builder::new("CMD");
+        for _ in 0..15 {
+            builder = builder.param("param");
+        }
+        assert!(builder.clone().try_build().is_ok());
+        assert_eq!(
+            Err(BuildError::TooManyParams(16)),
+            builder.trailing("trailing").try_build()
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_panicking_param() {
+        Builder::new("CMD").param("with space");
+    }
+
+    #[test]
+    fn test_tag_order() -> Result<(), Box<dyn std::error::Error>> {
+        let message = Builder::new("CMD")
+            .tag("z", "1")
+            .tag("a", "2")
+            .tag("m", "3")
+            .tag("a", "4")
+            .build();
+        assert_eq!("@z=1;a=4;m=3 CMD", message.to_string());
+
+        let raw = "@c=3;b=2;a=1;b=4 CMD";
+        let message = raw.parse::<Builder>()?.build();
+        assert_eq!("@c=3;b=4;a=1 CMD", message.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_str_invalid() {
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::InvalidCharacter,
+                Component::Params,
+                5,
+                1
+            )),
+            "CMD a\0b".parse::<Builder>()
+        );
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::InvalidCharacter,
+                Component::Params,
+                13,
+                1
+            )),
+            Message::from("PRIVMSG #c :a\0b").to_builder()
+        );
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::InvalidCharacter,
+                Component::Command,
+                1,
+                1
+            )),
+            Message::from("C\0MD").to_builder()
+        );
+    }
+}
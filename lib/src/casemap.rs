@@ -0,0 +1,347 @@
+//! Case-insensitive comparison of nicknames and channel names.
+//!
+//! Which characters are considered equal depends on the `CASEMAPPING` advertised by the
+//! server (see [ISupport::case_mapping](crate::isupport::ISupport::case_mapping)). Under
+//! the default `rfc1459` mapping `[]\~` are the lowercase forms of `{}|^`, so `#chan[1]`
+//! and `#CHAN{1}` name the same channel.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::casemap::{CaseInsensitiveName, CaseMapping};
+//! use irc_rust::Message;
+//! use std::collections::HashMap;
+//! # fn main() -> Result<(), irc_rust::errors::ParserError> {
+//! let mut topics = HashMap::new();
+//! topics.insert(CaseInsensitiveName::new("#Chan[1]", CaseMapping::Rfc1459), "Topic");
+//!
+//! let message = Message::from(":Nick!user@host PRIVMSG #chan{1} :Hello");
+//! let parsed = message.parse()?;
+//! let target = CaseInsensitiveName::new(parsed.param(0).unwrap(), CaseMapping::Rfc1459);
+//! assert_eq!(Some(&"Topic"), topics.get(&target));
+//! assert!(CaseInsensitiveName::new("nick", CaseMapping::Rfc1459) == parsed.prefix_name().unwrap());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::errors::UnknownNameError;
+use std::borrow::{Borrow, Cow};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// Casemappings advertised with `CASEMAPPING`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum CaseMapping {
+    /// Only `A-Z` are folded to `a-z`.
+    Ascii,
+    /// `A-Z[]\~` are folded to `a-z{}|^`.
+    #[default]
+    Rfc1459,
+    /// `A-Z[]\` are folded to `a-z{}|`.
+    StrictRfc1459,
+    /// Unicode case folding of
+    /// [RFC 7613](https://tools.ietf.org/html/rfc7613#section-3.2). Fullwidth characters are
+    /// mapped to their halfwidth forms and all characters are lowercased. Unicode
+    /// normalization isn't applied.
+    Rfc7613,
+}
+
+impl CaseMapping {
+    /// Returns the name used in `CASEMAPPING`.
+    pub fn name(self) -> &'static str {
+        match self {
+            CaseMapping::Ascii => "ascii",
+            CaseMapping::Rfc1459 => "rfc1459",
+            CaseMapping::StrictRfc1459 => "strict-rfc1459",
+            CaseMapping::Rfc7613 => "rfc7613",
+        }
+    }
+
+    /// Folds an ASCII character. Returns the character unchanged if it isn't affected.
+    fn fold_ascii(self, ch: char) -> char {
+        match (self, ch) {
+            (_, 'A'..='Z') => ch.to_ascii_lowercase(),
+            (CaseMapping::Rfc1459, '[') | (CaseMapping::StrictRfc1459, '[') => '{',
+            (CaseMapping::Rfc1459, ']') | (CaseMapping::StrictRfc1459, ']') => '}',
+            (CaseMapping::Rfc1459, '\\') | (CaseMapping::StrictRfc1459, '\\') => '|',
+            (CaseMapping::Rfc1459, '~') => '^',
+            _ => ch,
+        }
+    }
+
+    /// Returns true if folding changes the character.
+    fn changes(self, ch: char) -> bool {
+        if ch.is_ascii() {
+            self.fold_ascii(ch) != ch
+        } else {
+            self == CaseMapping::Rfc7613
+                && (halfwidth(ch) != ch || ch.to_lowercase().ne(std::iter::once(ch)))
+        }
+    }
+
+    /// Returns the lowercase form of **name**.
+    ///
+    /// Returns the name borrowed if it is already folded.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::casemap::CaseMapping;
+    ///
+    /// assert_eq!("nick{away}^", CaseMapping::Rfc1459.fold("Nick[Away]~"));
+    /// assert_eq!("nick{away}~", CaseMapping::StrictRfc1459.fold("Nick[Away]~"));
+    /// assert_eq!("nick[away]~", CaseMapping::Ascii.fold("Nick[Away]~"));
+    /// assert_eq!("straße", CaseMapping::Rfc7613.fold("STRAßE"));
+    /// ```
+    pub fn fold(self, name: &str) -> Cow<'_, str> {
+        if !name.chars().any(|ch| self.changes(ch)) {
+            return Cow::Borrowed(name);
+        }
+        let mut folded = String::with_capacity(name.len());
+        for ch in name.chars() {
+            if ch.is_ascii() {
+                folded.push(self.fold_ascii(ch));
+            } else if self == CaseMapping::Rfc7613 {
+                let ch = halfwidth(ch);
+                if ch.is_ascii() {
+                    folded.push(self.fold_ascii(ch));
+                } else {
+                    folded.extend(ch.to_lowercase());
+                }
+            } else {
+                folded.push(ch);
+            }
+        }
+        Cow::Owned(folded)
+    }
+
+    /// Returns true if both names are equal under this casemapping.
+    pub fn equals(self, left: &str, right: &str) -> bool {
+        self.fold(left) == self.fold(right)
+    }
+
+    /// Compares the folded forms of both names.
+    pub fn compare(self, left: &str, right: &str) -> Ordering {
+        self.fold(left).cmp(&self.fold(right))
+    }
+
+    /// Hashes the folded form of **name** so equal names result in equal hashes.
+    pub fn hash<H: Hasher>(self, name: &str, state: &mut H) {
+        self.fold(name).hash(state)
+    }
+}
+
+/// Maps fullwidth forms of ASCII characters to ASCII.
+fn halfwidth(ch: char) -> char {
+    match ch {
+        '\u{FF01}'..='\u{FF5E}' => std::char::from_u32(u32::from(ch) - 0xFF01 + 0x21).unwrap_or(ch),
+        '\u{3000}' => ' ',
+        _ => ch,
+    }
+}
+
+impl fmt::Display for CaseMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for CaseMapping {
+    type Err = UnknownNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ascii" => Ok(CaseMapping::Ascii),
+            "rfc1459" => Ok(CaseMapping::Rfc1459),
+            "strict-rfc1459" => Ok(CaseMapping::StrictRfc1459),
+            "rfc7613" => Ok(CaseMapping::Rfc7613),
+            _ => Err(UnknownNameError {
+                kind: "casemapping",
+                name: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// A nickname or channel name which compares, orders and hashes by its folded form.
+///
+/// Can be used as key of a [HashMap](std::collections::HashMap). As it borrows as the
+/// folded `str`, lookups can be done without allocating with [CaseMapping::fold]:
+/// `map.get(&*mapping.fold(name))`.
+///
+/// Compares equal to a `str` if the `str` folded with the casemapping of the name is equal.
+#[derive(Debug, Clone)]
+pub struct CaseInsensitiveName {
+    name: String,
+    folded: String,
+    mapping: CaseMapping,
+}
+
+impl CaseInsensitiveName {
+    pub fn new<S: ToString>(name: S, mapping: CaseMapping) -> Self {
+        let name = name.to_string();
+        let folded = mapping.fold(&name).into_owned();
+        CaseInsensitiveName {
+            name,
+            folded,
+            mapping,
+        }
+    }
+
+    /// Returns the name as it was created.
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the folded form of the name.
+    pub fn folded(&self) -> &str {
+        &self.folded
+    }
+
+    pub fn mapping(&self) -> CaseMapping {
+        self.mapping
+    }
+}
+
+impl PartialEq for CaseInsensitiveName {
+    fn eq(&self, other: &Self) -> bool {
+        self.folded == other.folded
+    }
+}
+
+impl Eq for CaseInsensitiveName {}
+
+impl PartialEq<str> for CaseInsensitiveName {
+    fn eq(&self, other: &str) -> bool {
+        self.folded == self.mapping.fold(other)
+    }
+}
+
+impl<'a> PartialEq<&'a str> for CaseInsensitiveName {
+    fn eq(&self, other: &&'a str) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialOrd for CaseInsensitiveName {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CaseInsensitiveName {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.folded.cmp(&other.folded)
+    }
+}
+
+impl Hash for CaseInsensitiveName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.folded.hash(state)
+    }
+}
+
+impl Borrow<str> for CaseInsensitiveName {
+    fn borrow(&self) -> &str {
+        &self.folded
+    }
+}
+
+impl fmt::Display for CaseInsensitiveName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::casemap::{CaseInsensitiveName, CaseMapping};
+    use crate::errors::UnknownNameError;
+    use std::borrow::Cow;
+    use std::cmp::Ordering;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+    use std::hash::Hasher;
+
+    #[test]
+    fn test_fold() {
+        assert!(matches!(
+            CaseMapping::Rfc1459.fold("nick{}|^"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!("ab{}|^", CaseMapping::Rfc1459.fold("AB[]\\~"));
+        assert_eq!("ab{}|~", CaseMapping::StrictRfc1459.fold("AB[]\\~"));
+        assert_eq!("ab[]\\~", CaseMapping::Ascii.fold("AB[]\\~"));
+        assert_eq!("ÄÖ", CaseMapping::Rfc1459.fold("ÄÖ"));
+        assert_eq!("äö[nick]", CaseMapping::Rfc7613.fold("ÄÖ[ＮＩＣＫ]"));
+    }
+
+    #[test]
+    fn test_compare() {
+        assert!(CaseMapping::Rfc1459.equals("#Chan[1]", "#chan{1}"));
+        assert!(!CaseMapping::Ascii.equals("#Chan[1]", "#chan{1}"));
+        assert!(!CaseMapping::StrictRfc1459.equals("nick~", "NICK^"));
+        assert_eq!(
+            Ordering::Equal,
+            CaseMapping::Rfc1459.compare("Nick~", "nick^")
+        );
+        assert_eq!(Ordering::Less, CaseMapping::Ascii.compare("Alice", "bob"));
+
+        let hash = |name: &str| {
+            let mut hasher = DefaultHasher::new();
+            CaseMapping::Rfc1459.hash(name, &mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash("Nick[a]"), hash("nick{A}"));
+    }
+
+    #[test]
+    fn test_name() {
+        for mapping in [
+            CaseMapping::Ascii,
+            CaseMapping::Rfc1459,
+            CaseMapping::StrictRfc1459,
+            CaseMapping::Rfc7613,
+        ]
+        .iter()
+        {
+            assert_eq!(Ok(*mapping), mapping.name().parse());
+        }
+        assert_eq!(
+            Err(UnknownNameError {
+                kind: "casemapping",
+                name: "unknown".to_string()
+            }),
+            "unknown".parse::<CaseMapping>()
+        );
+        assert_eq!(CaseMapping::Rfc1459, CaseMapping::default());
+    }
+
+    #[test]
+    fn test_case_insensitive_name() {
+        let mut channels = HashMap::new();
+        channels.insert(
+            CaseInsensitiveName::new("#Chan[1]", CaseMapping::Rfc1459),
+            1,
+        );
+        assert_eq!(
+            Some(&1),
+            channels.get(&CaseInsensitiveName::new("#CHAN{1}", CaseMapping::Rfc1459))
+        );
+        assert_eq!(
+            Some(&1),
+            channels.get(&*CaseMapping::Rfc1459.fold("#chan[1]"))
+        );
+        assert_eq!(None, channels.get("#Chan[1]"));
+
+        let name = CaseInsensitiveName::new("Nick[m]", CaseMapping::Rfc1459);
+        assert_eq!("Nick[m]", name.as_str());
+        assert_eq!("nick{m}", name.folded());
+        assert_eq!("Nick[m]", name.to_string());
+        assert!(name == "NICK{M}");
+        assert!(name != "other");
+        assert!(name < CaseInsensitiveName::new("Other", CaseMapping::Rfc1459));
+    }
+}
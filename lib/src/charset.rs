@@ -0,0 +1,463 @@
+//! Decoding and encoding of message text in legacy charsets.
+//!
+//! Old networks often carry text in single byte charsets next to UTF-8. Decoding tries UTF-8
+//! first and falls back to the configured [Charset] if the text isn't valid UTF-8. As the
+//! delimiters of a message are ASCII, which all supported charsets share, whole lines can
+//! be decoded and encoded at once. Tags are always UTF-8 as required by IRCv3.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::charset::{Charset, CharsetConfig};
+//! use irc_rust::raw::RawMessage;
+//! use irc_rust::Message;
+//!
+//! let config = CharsetConfig::new(Charset::Cp1252).channel("#russian", Charset::Koi8R);
+//!
+//! let message = RawMessage::from(&b"PRIVMSG #europe :caf\xe9 \x80"[..]);
+//! assert_eq!(Message::from("PRIVMSG #europe :café €"), config.decode(&message));
+//!
+//! let message = RawMessage::from(&b"PRIVMSG #russian :\xf0\xd2\xc9\xd7\xc5\xd4"[..]);
+//! assert_eq!(Message::from("PRIVMSG #russian :Привет"), config.decode(&message));
+//!
+//! // Valid UTF-8 is always decoded as such
+//! let message = RawMessage::from("PRIVMSG #russian :Привет");
+//! assert_eq!(Message::from("PRIVMSG #russian :Привет"), config.decode(&message));
+//! ```
+
+use crate::errors::UnknownNameError;
+use crate::message::Message;
+use crate::raw::RawMessage;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Characters of CP1252 for bytes 0x80 to 0x9F. Undefined bytes map to the C1 control
+/// characters like in Latin-1.
+const CP1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// Bytes of ISO-8859-15 differing from Latin-1 and their characters.
+const ISO8859_15_DIFF: [(u8, char); 8] = [
+    (0xA4, '\u{20AC}'),
+    (0xA6, '\u{0160}'),
+    (0xA8, '\u{0161}'),
+    (0xB4, '\u{017D}'),
+    (0xB8, '\u{017E}'),
+    (0xBC, '\u{0152}'),
+    (0xBD, '\u{0153}'),
+    (0xBE, '\u{0178}'),
+];
+
+/// Characters of KOI8-R for bytes 0x80 to 0xFF as defined in
+/// [RFC 1489](https://tools.ietf.org/html/rfc1489).
+const KOI8R_HIGH: [char; 128] = [
+    '\u{2500}', '\u{2502}', '\u{250C}', '\u{2510}', '\u{2514}', '\u{2518}', '\u{251C}', '\u{2524}',
+    '\u{252C}', '\u{2534}', '\u{253C}', '\u{2580}', '\u{2584}', '\u{2588}', '\u{258C}', '\u{2590}',
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2320}', '\u{25A0}', '\u{2219}', '\u{221A}', '\u{2248}',
+    '\u{2264}', '\u{2265}', '\u{00A0}', '\u{2321}', '\u{00B0}', '\u{00B2}', '\u{00B7}', '\u{00F7}',
+    '\u{2550}', '\u{2551}', '\u{2552}', '\u{0451}', '\u{2553}', '\u{2554}', '\u{2555}', '\u{2556}',
+    '\u{2557}', '\u{2558}', '\u{2559}', '\u{255A}', '\u{255B}', '\u{255C}', '\u{255D}', '\u{255E}',
+    '\u{255F}', '\u{2560}', '\u{2561}', '\u{0401}', '\u{2562}', '\u{2563}', '\u{2564}', '\u{2565}',
+    '\u{2566}', '\u{2567}', '\u{2568}', '\u{2569}', '\u{256A}', '\u{256B}', '\u{256C}', '\u{00A9}',
+    '\u{044E}', '\u{0430}', '\u{0431}', '\u{0446}', '\u{0434}', '\u{0435}', '\u{0444}', '\u{0433}',
+    '\u{0445}', '\u{0438}', '\u{0439}', '\u{043A}', '\u{043B}', '\u{043C}', '\u{043D}', '\u{043E}',
+    '\u{043F}', '\u{044F}', '\u{0440}', '\u{0441}', '\u{0442}', '\u{0443}', '\u{0436}', '\u{0432}',
+    '\u{044C}', '\u{044B}', '\u{0437}', '\u{0448}', '\u{044D}', '\u{0449}', '\u{0447}', '\u{044A}',
+    '\u{042E}', '\u{0410}', '\u{0411}', '\u{0426}', '\u{0414}', '\u{0415}', '\u{0424}', '\u{0413}',
+    '\u{0425}', '\u{0418}', '\u{0419}', '\u{041A}', '\u{041B}', '\u{041C}', '\u{041D}', '\u{041E}',
+    '\u{041F}', '\u{042F}', '\u{0420}', '\u{0421}', '\u{0422}', '\u{0423}', '\u{0416}', '\u{0412}',
+    '\u{042C}', '\u{042B}', '\u{0417}', '\u{0428}', '\u{042D}', '\u{0429}', '\u{0427}', '\u{042A}',
+];
+
+/// Charsets supported for decoding and encoding message text.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum Charset {
+    #[default]
+    Utf8,
+    /// ISO-8859-1
+    Latin1,
+    /// Windows-1252, a superset of Latin-1 with printable characters in 0x80 to 0x9F.
+    Cp1252,
+    /// Latin-9, Latin-1 with the Euro sign and some French and Finnish letters.
+    Iso8859_15,
+    Koi8R,
+}
+
+impl Charset {
+    /// Returns the character of a byte. Only called with non-ASCII bytes.
+    fn decode_byte(self, byte: u8) -> char {
+        match self {
+            // Not called as UTF-8 isn't decoded byte by byte
+            Charset::Utf8 => char::REPLACEMENT_CHARACTER,
+            Charset::Latin1 => char::from(byte),
+            Charset::Cp1252 => match byte {
+                0x80..=0x9F => CP1252_HIGH[usize::from(byte - 0x80)],
+                _ => char::from(byte),
+            },
+            Charset::Iso8859_15 => ISO8859_15_DIFF
+                .iter()
+                .find(|(diff, _)| *diff == byte)
+                .map(|(_, ch)| *ch)
+                .unwrap_or_else(|| char::from(byte)),
+            Charset::Koi8R => KOI8R_HIGH[usize::from(byte - 0x80)],
+        }
+    }
+
+    /// Returns the byte of a non-ASCII character if it is mappable.
+    fn encode_char(self, ch: char) -> Option<u8> {
+        let code = u32::from(ch);
+        match self {
+            Charset::Utf8 => None,
+            Charset::Latin1 if code <= 0xFF => Some(code as u8),
+            Charset::Latin1 => None,
+            Charset::Cp1252 => CP1252_HIGH
+                .iter()
+                .position(|high| *high == ch)
+                .map(|pos| 0x80 + pos as u8)
+                .or_else(|| {
+                    if (0xA0..=0xFF).contains(&code) {
+                        Some(code as u8)
+                    } else {
+                        None
+                    }
+                }),
+            Charset::Iso8859_15 => {
+                if let Some((byte, _)) = ISO8859_15_DIFF.iter().find(|(_, diff)| *diff == ch) {
+                    Some(*byte)
+                } else if code <= 0xFF
+                    && !ISO8859_15_DIFF
+                        .iter()
+                        .any(|(byte, _)| u32::from(*byte) == code)
+                {
+                    Some(code as u8)
+                } else {
+                    None
+                }
+            }
+            Charset::Koi8R => KOI8R_HIGH
+                .iter()
+                .position(|high| *high == ch)
+                .map(|pos| 0x80 + pos as u8),
+        }
+    }
+
+    /// Decodes text in this charset. Invalid UTF-8 is replaced by `U+FFFD`.
+    ///
+    /// Returns the text borrowed if it is ASCII or valid UTF-8 for [Charset::Utf8].
+    pub fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        if self == Charset::Utf8 {
+            return String::from_utf8_lossy(bytes);
+        }
+        if bytes.is_ascii() {
+            // ASCII is valid UTF-8
+            return String::from_utf8_lossy(bytes);
+        }
+        Cow::Owned(
+            bytes
+                .iter()
+                .map(|byte| {
+                    if byte.is_ascii() {
+                        char::from(*byte)
+                    } else {
+                        self.decode_byte(*byte)
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Encodes text in this charset. Characters which can't be represented are replaced
+    /// by `?`.
+    ///
+    /// Returns the text borrowed if it is ASCII or the charset is [Charset::Utf8].
+    pub fn encode(self, text: &str) -> Cow<'_, [u8]> {
+        if self == Charset::Utf8 || text.is_ascii() {
+            return Cow::Borrowed(text.as_bytes());
+        }
+        Cow::Owned(
+            text.chars()
+                .map(|ch| {
+                    if ch.is_ascii() {
+                        ch as u8
+                    } else {
+                        self.encode_char(ch).unwrap_or(b'?')
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the preferred MIME name of the charset.
+    pub fn name(self) -> &'static str {
+        match self {
+            Charset::Utf8 => "UTF-8",
+            Charset::Latin1 => "ISO-8859-1",
+            Charset::Cp1252 => "windows-1252",
+            Charset::Iso8859_15 => "ISO-8859-15",
+            Charset::Koi8R => "KOI8-R",
+        }
+    }
+}
+
+impl Display for Charset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Charset {
+    type Err = UnknownNameError;
+
+    /// Parses the common names of the charsets case-insensitively.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_ascii_lowercase().replace('_', "-");
+        match name.as_str() {
+            "utf-8" | "utf8" => Ok(Charset::Utf8),
+            "iso-8859-1" | "latin1" | "latin-1" => Ok(Charset::Latin1),
+            "windows-1252" | "cp1252" => Ok(Charset::Cp1252),
+            "iso-8859-15" | "latin9" | "latin-9" => Ok(Charset::Iso8859_15),
+            "koi8-r" | "koi8r" => Ok(Charset::Koi8R),
+            _ => Err(UnknownNameError {
+                kind: "charset",
+                name: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Decodes text as UTF-8 or in the **fallback** charset if it isn't valid UTF-8.
+///
+/// Returns the text borrowed if it is valid UTF-8.
+pub fn decode(bytes: &[u8], fallback: Charset) -> Cow<'_, str> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => fallback.decode(bytes),
+    }
+}
+
+/// Splits a line into the tags section including the trailing space and the rest.
+fn split_tags(line: &[u8]) -> (&[u8], &[u8]) {
+    if !line.starts_with(b"@") {
+        return (&[], line);
+    }
+    let end = line
+        .iter()
+        .position(|byte| *byte == b' ')
+        .map(|pos| pos + 1)
+        .unwrap_or(line.len());
+    line.split_at(end)
+}
+
+/// Decodes a line as UTF-8 or in the **fallback** charset. Words are decoded separately to
+/// support lines mixing both. Tags are always decoded as UTF-8.
+pub(crate) fn decode_line(line: &[u8], fallback: Charset) -> String {
+    let (tags, rest) = split_tags(line);
+    let mut decoded = String::from_utf8_lossy(tags).into_owned();
+    for (index, word) in rest.split(|byte| *byte == b' ').enumerate() {
+        if index > 0 {
+            decoded.push(' ');
+        }
+        decoded.push_str(&decode(word, fallback));
+    }
+    decoded
+}
+
+/// Encodes a line in the charset. Tags are always encoded as UTF-8.
+pub(crate) fn encode_line(line: &str, charset: Charset) -> Vec<u8> {
+    let (tags, _) = split_tags(line.as_bytes());
+    let mut encoded = tags.to_vec();
+    // Splitting at a space keeps both parts valid UTF-8
+    encoded.extend_from_slice(&charset.encode(&line[tags.len()..]));
+    encoded
+}
+
+/// Charsets to use for a network with overrides for single channels.
+///
+/// Create one configuration per network. Channel names are compared ASCII
+/// case-insensitively.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CharsetConfig {
+    fallback: Charset,
+    channels: HashMap<String, Charset>,
+}
+
+impl CharsetConfig {
+    /// Creates a configuration using **fallback** for decoding text which isn't UTF-8 and
+    /// for encoding text.
+    pub fn new(fallback: Charset) -> Self {
+        CharsetConfig {
+            fallback,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Sets the charset of a channel.
+    pub fn channel<S: ToString>(mut self, channel: S, charset: Charset) -> Self {
+        self.channels
+            .insert(channel.to_string().to_ascii_lowercase(), charset);
+        self
+    }
+
+    /// Returns the charset of the network.
+    pub fn fallback(&self) -> Charset {
+        self.fallback
+    }
+
+    /// Returns the charset of the channel or the charset of the network if the channel has
+    /// none configured.
+    pub fn charset_for(&self, channel: &str) -> Charset {
+        self.channels
+            .get(&channel.to_ascii_lowercase())
+            .copied()
+            .unwrap_or(self.fallback)
+    }
+
+    /// Returns the charset of the first param naming a configured channel.
+    fn charset_for_params<'a, I: Iterator<Item = &'a [u8]>>(&self, mut params: I) -> Charset {
+        params
+            .find_map(|param| {
+                let channel = std::str::from_utf8(param).ok()?;
+                self.channels.get(&channel.to_ascii_lowercase()).copied()
+            })
+            .unwrap_or(self.fallback)
+    }
+
+    /// Decodes a message as UTF-8 or in the charset of the first channel in its params if
+    /// it isn't valid UTF-8. Words are decoded separately to support lines mixing both.
+    pub fn decode(&self, message: &RawMessage) -> Message {
+        if let Ok(text) = message.to_str() {
+            return Message::from(text);
+        }
+        let charset = match message.params() {
+            Ok(params) => self.charset_for_params(params),
+            Err(_) => self.fallback,
+        };
+        Message::from(decode_line(message.as_bytes(), charset))
+    }
+
+    /// Encodes a message in the charset of the first channel in its params.
+    pub fn encode(&self, message: &Message) -> RawMessage {
+        let charset = match message.params() {
+            Ok(params) => self.charset_for_params(params.map(str::as_bytes)),
+            Err(_) => self.fallback,
+        };
+        RawMessage::from(encode_line(&message.to_string(), charset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::charset::{decode, Charset, CharsetConfig};
+    use crate::errors::UnknownNameError;
+    use crate::raw::RawMessage;
+    use crate::Message;
+    use std::borrow::Cow;
+
+    const CHARSETS: [Charset; 4] = [
+        Charset::Latin1,
+        Charset::Cp1252,
+        Charset::Iso8859_15,
+        Charset::Koi8R,
+    ];
+
+    #[test]
+    fn test_decode() {
+        assert_eq!("Grüße", Charset::Latin1.decode(b"Gr\xfc\xdfe"));
+        assert_eq!("„€“", Charset::Cp1252.decode(b"\x84\x80\x93"));
+        assert_eq!("€Š", Charset::Iso8859_15.decode(b"\xa4\xa6"));
+        assert_eq!("¤¦", Charset::Latin1.decode(b"\xa4\xa6"));
+        assert_eq!("Привет", Charset::Koi8R.decode(b"\xf0\xd2\xc9\xd7\xc5\xd4"));
+        assert_eq!("\u{FFFD}", Charset::Utf8.decode(b"\xff"));
+        assert!(matches!(Charset::Koi8R.decode(b"ascii"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_decode_fallback() {
+        assert!(matches!(
+            decode("Grüße".as_bytes(), Charset::Latin1),
+            Cow::Borrowed("Grüße")
+        ));
+        assert_eq!("Grüße", decode(b"Gr\xfc\xdfe", Charset::Latin1));
+        assert_eq!("Grюяe", decode(b"Gr\xc0\xd1e", Charset::Koi8R));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for charset in CHARSETS.iter() {
+            let bytes = (0x20..=0xFF).collect::<Vec<u8>>();
+            let text = charset.decode(&bytes);
+            assert_eq!(bytes, charset.encode(&text).into_owned(), "{}", charset);
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(&b"caf\xe9 ?"[..], &Charset::Latin1.encode("café €")[..]);
+        assert_eq!(&b"caf\xe9 \x80"[..], &Charset::Cp1252.encode("café €")[..]);
+        assert_eq!(
+            &b"caf\xe9 \xa4 ?"[..],
+            &Charset::Iso8859_15.encode("café € ¤")[..]
+        );
+        assert_eq!(&b"\xf0\xd2\xc9 ?"[..], &Charset::Koi8R.encode("При é")[..]);
+        assert!(matches!(Charset::Latin1.encode("ascii"), Cow::Borrowed(_)));
+        assert_eq!("é".as_bytes(), &Charset::Utf8.encode("é")[..]);
+    }
+
+    #[test]
+    fn test_names() {
+        for charset in CHARSETS.iter().chain(Some(&Charset::Utf8)) {
+            assert_eq!(Ok(*charset), charset.name().parse());
+        }
+        assert_eq!(Ok(Charset::Cp1252), "CP1252".parse());
+        assert_eq!(
+            Err(UnknownNameError {
+                kind: "charset",
+                name: "ebcdic".to_string()
+            }),
+            "ebcdic".parse::<Charset>()
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let config = CharsetConfig::new(Charset::Latin1).channel("#Russian", Charset::Koi8R);
+        assert_eq!(Charset::Koi8R, config.charset_for("#russian"));
+        assert_eq!(Charset::Latin1, config.charset_for("#other"));
+
+        // Topic reply has the channel as second param
+        let message = RawMessage::from(&b"332 nick #RUSSIAN :\xf0\xd2\xc9"[..]);
+        assert_eq!(
+            Message::from("332 nick #RUSSIAN :При"),
+            config.decode(&message)
+        );
+        let message = RawMessage::from(&b"PRIVMSG nick :\xe4"[..]);
+        assert_eq!(Message::from("PRIVMSG nick :ä"), config.decode(&message));
+
+        let message = Message::from("PRIVMSG #russian :При");
+        assert_eq!(
+            &b"PRIVMSG #russian :\xf0\xd2\xc9"[..],
+            config.encode(&message).as_bytes()
+        );
+        let message = Message::from("PRIVMSG nick :ä");
+        assert_eq!(
+            &b"PRIVMSG nick :\xe4"[..],
+            config.encode(&message).as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_tags_stay_utf8() {
+        let config = CharsetConfig::new(Charset::Latin1);
+        let line = "@label=ä PRIVMSG #c :ä";
+        let encoded = config.encode(&Message::from(line));
+        assert_eq!(&b"@label=\xc3\xa4 PRIVMSG #c :\xe4"[..], encoded.as_bytes());
+        assert_eq!(Message::from(line), config.decode(&encoded));
+    }
+}
@@ -0,0 +1,518 @@
+//! Sans-IO state machine for the client side of the connection registration.
+//!
+//! [ClientState] consumes inbound [Message]s and returns the [Message]s to send in response
+//! without performing any I/O itself. It covers `PASS`/`NICK`/`USER` registration,
+//! [capability negotiation](https://ircv3.net/specs/extensions/capability-negotiation) with
+//! `CAP LS 302`, replying to `PING`s and falling back to alternative nicknames if the
+//! nickname is already in use.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::client::{ClientConfig, ClientState, Event};
+//! use irc_rust::Message;
+//! # fn main() -> Result<(), irc_rust::errors::ParserError> {
+//! let mut client = ClientState::new(ClientConfig::new("nick", "user", "Real Name"));
+//!
+//! // Send these to the server
+//! let outbound = client.start().unwrap();
+//! assert_eq!("CAP LS 302", outbound[0].to_string());
+//!
+//! // Feed everything received from the server into the state machine
+//! client.handle(&Message::from(":irc.example.net CAP * LS :multi-prefix"))?;
+//! client.handle(&Message::from(":irc.example.net 001 nick :Welcome"))?;
+//! assert!(client.is_registered());
+//! assert_eq!(Some(Event::Registered { nickname: "nick".to_string() }), client.poll_event());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::builder;
+use crate::command::Command;
+use crate::errors::{BuildError, ParserError};
+use crate::isupport::ISupport;
+use crate::numeric::Numeric;
+use crate::parsed::Parsed;
+use crate::Message;
+use std::collections::VecDeque;
+
+/// Configuration of the registration of a client.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ClientConfig {
+    pub nickname: String,
+    /// Nicknames tried in order if the nickname is already in use. If all are in use `_`
+    /// is appended to the last tried nickname.
+    pub alt_nicknames: Vec<String>,
+    pub username: String,
+    pub realname: String,
+    pub password: Option<String>,
+    /// Capabilities requested if the server offers them.
+    pub capabilities: Vec<String>,
+}
+
+impl ClientConfig {
+    /// Creates a configuration without password, alternative nicknames and capabilities.
+    pub fn new<SN, SU, SR>(nickname: SN, username: SU, realname: SR) -> Self
+    where
+        SN: ToString,
+        SU: ToString,
+        SR: ToString,
+    {
+        ClientConfig {
+            nickname: nickname.to_string(),
+            alt_nicknames: Vec::new(),
+            username: username.to_string(),
+            realname: realname.to_string(),
+            password: None,
+            capabilities: Vec::new(),
+        }
+    }
+}
+
+/// Events reported by [ClientState::poll_event].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Event {
+    /// The server acknowledged the requested capabilities.
+    CapabilitiesAcknowledged(Vec<String>),
+    /// The server rejected the requested capabilities.
+    CapabilitiesRejected(Vec<String>),
+    /// The nickname was in use. Registration continues with the fallback nickname.
+    NicknameInUse { nickname: String, fallback: String },
+    /// Registration completed with `RPL_WELCOME` and the nickname assigned by the server.
+    Registered { nickname: String },
+}
+
+/// Current phase of the registration.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Registration {
+    /// [ClientState::start] hasn't been called yet.
+    Idle,
+    /// Waiting for the list of capabilities.
+    CapabilityListing,
+    /// Waiting for the answer to the capability request.
+    CapabilityRequesting,
+    /// Capability negotiation has ended, waiting for `RPL_WELCOME`.
+    Registering,
+    /// `RPL_WELCOME` has been received.
+    Registered,
+}
+
+/// Sans-IO state machine of the client registration. See the module documentation for usage.
+#[derive(Debug, Clone)]
+pub struct ClientState {
+    config: ClientConfig,
+    registration: Registration,
+    nickname: String,
+    next_alt_nickname: usize,
+    available_capabilities: Vec<String>,
+    enabled_capabilities: Vec<String>,
+    isupport: ISupport,
+    events: VecDeque<Event>,
+}
+
+impl ClientState {
+    pub fn new(config: ClientConfig) -> Self {
+        ClientState {
+            nickname: config.nickname.clone(),
+            config,
+            registration: Registration::Idle,
+            next_alt_nickname: 0,
+            available_capabilities: Vec::new(),
+            enabled_capabilities: Vec::new(),
+            isupport: ISupport::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Starts the registration and returns the messages to send first.
+    ///
+    /// Fails if a nickname or the username of the configuration isn't a valid param or the
+    /// password or realname contain CR, LF or NUL.
+    pub fn start(&mut self) -> Result<Vec<Message>, BuildError> {
+        for nickname in &self.config.alt_nicknames {
+            Self::nick(nickname)?;
+        }
+        let mut outbound = vec![Message::builder(Command::Cap)
+            .param("LS")
+            .param("302")
+            .build()];
+        if let Some(password) = &self.config.password {
+            let pass = Message::builder(Command::Pass);
+            let pass = if builder::needs_trailing(password) {
+                pass.try_trailing(password)?
+            } else {
+                pass.try_param(password)?
+            };
+            outbound.push(pass.build());
+        }
+        outbound.push(Self::nick(&self.nickname)?);
+        outbound.push(
+            Message::builder(Command::User)
+                .try_param(&self.config.username)?
+                .param("0")
+                .param("*")
+                .try_trailing(&self.config.realname)?
+                .build(),
+        );
+        self.registration = Registration::CapabilityListing;
+        Ok(outbound)
+    }
+
+    /// Processes an inbound message and returns the messages to send in response.
+    pub fn handle(&mut self, message: &Message) -> Result<Vec<Message>, ParserError> {
+        let parsed = message.parse()?;
+        let mut outbound = Vec::new();
+        match parsed.typed_command() {
+            Some(Command::Ping) => {
+                // Fails for tokens which can't be sent back like ones containing NUL
+                message.to_builder()?;
+                outbound.push(Self::pong(&parsed));
+            }
+            Some(Command::Cap) => self.handle_cap(&parsed, &mut outbound),
+            Some(Command::Nick) => self.handle_nick(&parsed),
+            Some(Command::Numeric(Numeric::RPL_WELCOME)) => self.handle_welcome(&parsed),
+            Some(Command::Numeric(Numeric::RPL_ISUPPORT)) => {
+                // Can't fail as the command has been checked
+                let _ = self.isupport.add(&parsed);
+            }
+            Some(Command::Numeric(Numeric::ERR_NICKNAMEINUSE))
+            | Some(Command::Numeric(Numeric::ERR_NICKCOLLISION))
+                if self.registration != Registration::Registered =>
+            {
+                outbound.push(self.fallback_nickname())
+            }
+            _ => {}
+        }
+        Ok(outbound)
+    }
+
+    /// Returns the next event if any occurred.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Returns the current phase of the registration.
+    pub fn registration(&self) -> Registration {
+        self.registration
+    }
+
+    /// Returns true if `RPL_WELCOME` has been received.
+    pub fn is_registered(&self) -> bool {
+        self.registration == Registration::Registered
+    }
+
+    /// Returns the current nickname of the client.
+    pub fn nickname(&self) -> &str {
+        &self.nickname
+    }
+
+    /// Returns the capabilities offered by the server.
+    pub fn available_capabilities(&self) -> &[String] {
+        &self.available_capabilities
+    }
+
+    /// Returns the capabilities acknowledged by the server.
+    pub fn enabled_capabilities(&self) -> &[String] {
+        &self.enabled_capabilities
+    }
+
+    /// Returns the parameters advertised by the server with `RPL_ISUPPORT`.
+    pub fn isupport(&self) -> &ISupport {
+        &self.isupport
+    }
+
+    fn pong(parsed: &Parsed) -> Message {
+        let mut builder = Message::builder(Command::Pong);
+        // Repeated spaces result in empty params
+        for param in parsed.params().flatten().filter(|param| !param.is_empty()) {
+            builder = builder.param(param);
+        }
+        if let Some(trailing) = parsed.trailing() {
+            builder = builder.trailing(trailing);
+        }
+        builder.build()
+    }
+
+    fn cap_end() -> Message {
+        Message::builder(Command::Cap).param("END").build()
+    }
+
+    fn handle_cap(&mut self, parsed: &Parsed, outbound: &mut Vec<Message>) {
+        // :server CAP <nick> <subcommand> [*] :<capabilities>
+        let params = parsed.params().flatten().copied().collect::<Vec<_>>();
+        let subcommand = match params.get(1) {
+            Some(subcommand) => subcommand.to_ascii_uppercase(),
+            None => return,
+        };
+        let more = parsed.trailing().is_some() && params.get(2) == Some(&"*");
+        let capabilities = parsed
+            .trailing()
+            .or_else(|| params.get(2).copied())
+            .unwrap_or("")
+            .split(' ')
+            .filter(|capability| !capability.is_empty())
+            .map(|capability| capability.to_string())
+            .collect::<Vec<_>>();
+
+        match subcommand.as_str() {
+            "LS" if self.registration == Registration::CapabilityListing => {
+                self.available_capabilities.extend(capabilities);
+                if more {
+                    return;
+                }
+                let requested = self
+                    .config
+                    .capabilities
+                    .iter()
+                    .filter(|wanted| {
+                        self.available_capabilities
+                            .iter()
+                            .any(|available| available.split('=').next() == Some(wanted.as_str()))
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if requested.is_empty() {
+                    self.registration = Registration::Registering;
+                    outbound.push(Self::cap_end());
+                } else {
+                    self.registration = Registration::CapabilityRequesting;
+                    outbound.push(
+                        Message::builder(Command::Cap)
+                            .param("REQ")
+                            .trailing(requested.join(" "))
+                            .build(),
+                    );
+                }
+            }
+            "ACK" => {
+                for capability in &capabilities {
+                    if capability.starts_with('-') {
+                        self.enabled_capabilities
+                            .retain(|enabled| *enabled != capability[1..]);
+                    } else if !self.enabled_capabilities.contains(capability) {
+                        self.enabled_capabilities.push(capability.clone());
+                    }
+                }
+                self.events
+                    .push_back(Event::CapabilitiesAcknowledged(capabilities));
+                self.end_negotiation(outbound);
+            }
+            "NAK" => {
+                self.events
+                    .push_back(Event::CapabilitiesRejected(capabilities));
+                self.end_negotiation(outbound);
+            }
+            _ => {}
+        }
+    }
+
+    fn end_negotiation(&mut self, outbound: &mut Vec<Message>) {
+        if self.registration == Registration::CapabilityRequesting {
+            self.registration = Registration::Registering;
+            outbound.push(Self::cap_end());
+        }
+    }
+
+    fn handle_nick(&mut self, parsed: &Parsed) {
+        let own = parsed.prefix_name() == Some(self.nickname.as_str());
+        let nickname = parsed.param(0).or_else(|| parsed.trailing());
+        if let (true, Some(nickname)) = (own, nickname) {
+            self.set_nickname(nickname);
+        }
+    }
+
+    fn handle_welcome(&mut self, parsed: &Parsed) {
+        if let Some(nickname) = parsed.param(0) {
+            self.set_nickname(nickname);
+        }
+        if self.registration != Registration::Registered {
+            self.registration = Registration::Registered;
+            self.events.push_back(Event::Registered {
+                nickname: self.nickname.clone(),
+            });
+        }
+    }
+
+    /// Keeps the nickname assigned by the server unless it can't be sent back like `a b`.
+    fn set_nickname(&mut self, nickname: &str) {
+        if Self::nick(nickname).is_ok() {
+            self.nickname = nickname.to_string();
+        }
+    }
+
+    fn nick(nickname: &str) -> Result<Message, BuildError> {
+        Message::builder(Command::Nick)
+            .try_param(nickname)
+            .map(|builder| builder.build())
+    }
+
+    /// The alternative nicknames are checked in [ClientState::start] and the current one in
+    /// [ClientState::set_nickname], so the fallback is always a valid param.
+    fn fallback_nickname(&mut self) -> Message {
+        let fallback = match self.config.alt_nicknames.get(self.next_alt_nickname) {
+            Some(alt) => {
+                self.next_alt_nickname += 1;
+                alt.clone()
+            }
+            None => format!("{}_", self.nickname),
+        };
+        self.events.push_back(Event::NicknameInUse {
+            nickname: std::mem::replace(&mut self.nickname, fallback.clone()),
+            fallback,
+        });
+        Message::builder(Command::Nick)
+            .param(&self.nickname)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::{ClientConfig, ClientState, Event, Registration};
+    use crate::Message;
+    use std::error::Error;
+
+    fn to_strings(messages: Vec<Message>) -> Vec<String> {
+        messages.iter().map(|message| message.to_string()).collect()
+    }
+
+    #[test]
+    fn test_registration_with_capabilities() -> Result<(), Box<dyn Error>> {
+        let mut config = ClientConfig::new("nick", "user", "Real Name");
+        config.password = Some("secret".to_string());
+        config.capabilities = vec!["multi-prefix".to_string(), "sasl".to_string()];
+        let mut client = ClientState::new(config);
+
+        assert_eq!(
+            vec![
+                "CAP LS 302",
+                "PASS secret",
+                "NICK nick",
+                "USER user 0 * :Real Name"
+            ],
+            to_strings(client.start()?)
+        );
+
+        let outbound =
+            client.handle(&Message::from(":srv CAP * LS * :multi-prefix away-notify"))?;
+        assert!(outbound.is_empty());
+        let outbound = client.handle(&Message::from(":srv CAP * LS :sasl=PLAIN,EXTERNAL"))?;
+        assert_eq!(vec!["CAP REQ :multi-prefix sasl"], to_strings(outbound));
+        assert_eq!(Registration::CapabilityRequesting, client.registration());
+
+        let outbound = client.handle(&Message::from(":srv CAP nick ACK :multi-prefix sasl"))?;
+        assert_eq!(vec!["CAP END"], to_strings(outbound));
+        assert_eq!(
+            &["multi-prefix".to_string(), "sasl".to_string()][..],
+            client.enabled_capabilities()
+        );
+        assert_eq!(
+            Some(Event::CapabilitiesAcknowledged(vec![
+                "multi-prefix".to_string(),
+                "sasl".to_string()
+            ])),
+            client.poll_event()
+        );
+
+        client.handle(&Message::from(":srv 001 nick :Welcome"))?;
+        assert!(client.is_registered());
+        assert_eq!(
+            Some(Event::Registered {
+                nickname: "nick".to_string()
+            }),
+            client.poll_event()
+        );
+        assert_eq!(None, client.poll_event());
+
+        client.handle(&Message::from(
+            ":srv 005 nick NICKLEN=30 NETWORK=Example :are supported by this server",
+        ))?;
+        assert_eq!(30, client.isupport().nick_len());
+        assert_eq!(Some("Example"), client.isupport().network());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_common_capabilities() -> Result<(), Box<dyn Error>> {
+        let mut client = ClientState::new(ClientConfig::new("nick", "user", "Real Name"));
+        client.start()?;
+        let outbound = client.handle(&Message::from(":srv CAP * LS :multi-prefix"))?;
+        assert_eq!(vec!["CAP END"], to_strings(outbound));
+        assert_eq!(Registration::Registering, client.registration());
+        Ok(())
+    }
+
+    #[test]
+    fn test_password_as_trailing() {
+        for (password, expected) in &[("", "PASS :"), ("a b", "PASS :a b"), (":a", "PASS ::a")] {
+            let mut config = ClientConfig::new("nick", "user", "Real Name");
+            config.password = Some(password.to_string());
+            let outbound = to_strings(ClientState::new(config).start().unwrap());
+            assert_eq!(*expected, outbound[1]);
+        }
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for (nickname, username, alt) in &[
+            ("a b", "user", "alt"),
+            ("nick", "", "alt"),
+            ("nick", "user", ":alt"),
+        ] {
+            let mut config = ClientConfig::new(*nickname, *username, "Real Name");
+            config.alt_nicknames = vec![alt.to_string()];
+            let mut client = ClientState::new(config);
+            assert!(client.start().is_err());
+            assert_eq!(Registration::Idle, client.registration());
+        }
+    }
+
+    #[test]
+    fn test_ping() -> Result<(), Box<dyn Error>> {
+        let mut client = ClientState::new(ClientConfig::new("nick", "user", "Real Name"));
+        let outbound = client.handle(&Message::from("PING :irc.example.net"))?;
+        assert_eq!(vec!["PONG :irc.example.net"], to_strings(outbound));
+        let outbound = client.handle(&Message::from("PING token"))?;
+        assert_eq!(vec!["PONG token"], to_strings(outbound));
+        let outbound = client.handle(&Message::from("PING  token"))?;
+        assert_eq!(vec!["PONG token"], to_strings(outbound));
+        assert!(client.handle(&Message::from("PING :a\0b")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_nickname_in_use() -> Result<(), Box<dyn Error>> {
+        let mut config = ClientConfig::new("nick", "user", "Real Name");
+        config.alt_nicknames = vec!["nick2".to_string()];
+        let mut client = ClientState::new(config);
+        client.start()?;
+
+        let outbound = client.handle(&Message::from(":srv 433 * nick :Nickname is in use"))?;
+        assert_eq!(vec!["NICK nick2"], to_strings(outbound));
+        assert_eq!(
+            Some(Event::NicknameInUse {
+                nickname: "nick".to_string(),
+                fallback: "nick2".to_string()
+            }),
+            client.poll_event()
+        );
+        let outbound = client.handle(&Message::from(":srv 433 * nick2 :Nickname is in use"))?;
+        assert_eq!(vec!["NICK nick2_"], to_strings(outbound));
+        assert_eq!("nick2_", client.nickname());
+
+        client.handle(&Message::from(":srv 001 nick2_ :Welcome"))?;
+        assert!(client.is_registered());
+
+        // Nickname changes after registration are tracked but not retried
+        let outbound = client.handle(&Message::from(":srv 433 nick2_ other :In use"))?;
+        assert!(outbound.is_empty());
+        client.handle(&Message::from(":nick2_!user@host NICK :other"))?;
+        assert_eq!("other", client.nickname());
+
+        // Nicknames which can't be sent back are ignored
+        client.handle(&Message::from(":other!user@host NICK :a b"))?;
+        assert_eq!("other", client.nickname());
+        Ok(())
+    }
+}
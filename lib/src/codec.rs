@@ -0,0 +1,260 @@
+//! Framing of IRC lines for [tokio](https://tokio.rs) transports.
+//!
+//! Requires the `tokio` feature.
+
+use crate::builder::Builder;
+use crate::errors::CodecError;
+use crate::Message;
+use bytes::{BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Maximum length of the tags section including the leading `@` and the trailing space
+/// as defined by the [IRCv3 message-tags specification](https://ircv3.net/specs/extensions/message-tags#size-limit).
+pub const MAX_TAGS_LENGTH: usize = 8191;
+
+/// Maximum length of the rest of a line including the CRLF line ending as defined by
+/// [RFC 2812](https://tools.ietf.org/html/rfc2812#section-2.3).
+pub const MAX_MESSAGE_LENGTH: usize = 512;
+
+/// Codec splitting incoming bytes into [Message]s and encoding [Message]s and [Builder]s
+/// followed by CRLF.
+///
+/// Incoming lines may be terminated by CRLF or a single LF. Empty lines are skipped.
+/// Line lengths are measured including the line ending. Encoding a line containing a `\r`,
+/// `\n` or `\0` fails instead of sending more than one line.
+///
+/// # Usage
+///
+/// ```rust
+/// use bytes::BytesMut;
+/// use irc_rust::codec::IrcCodec;
+/// use irc_rust::Message;
+/// use tokio_util::codec::{Decoder, Encoder};
+/// # fn main() -> Result<(), irc_rust::errors::CodecError> {
+/// let mut codec = IrcCodec::new();
+/// let mut buf = BytesMut::from("PING :server\r\nPRIV");
+/// assert_eq!(Some(Message::from("PING :server")), codec.decode(&mut buf)?);
+/// assert_eq!(None, codec.decode(&mut buf)?);
+///
+/// let mut out = BytesMut::new();
+/// codec.encode(Message::builder("PONG").trailing("server"), &mut out)?;
+/// assert_eq!(&b"PONG :server\r\n"[..], &out[..]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IrcCodec {
+    max_line_length: usize,
+    /// Index in the buffer up to which no line ending has been found yet.
+    next_index: usize,
+    /// Set if the rest of a too long line has to be skipped.
+    discarding: bool,
+}
+
+impl IrcCodec {
+    /// Creates a codec allowing lines of up to [MAX_TAGS_LENGTH] + [MAX_MESSAGE_LENGTH] bytes.
+    pub fn new() -> Self {
+        IrcCodec::with_max_line_length(MAX_TAGS_LENGTH + MAX_MESSAGE_LENGTH)
+    }
+
+    /// Creates a codec with a custom maximum line length including the line ending.
+    pub fn with_max_line_length(max_line_length: usize) -> Self {
+        IrcCodec {
+            max_line_length,
+            next_index: 0,
+            discarding: false,
+        }
+    }
+
+    /// Returns the maximum line length including the line ending.
+    pub fn max_line_length(&self) -> usize {
+        self.max_line_length
+    }
+
+    fn too_long(&self, length: usize) -> CodecError {
+        CodecError::LineTooLong {
+            length,
+            max: self.max_line_length,
+        }
+    }
+
+    fn encode_line(&self, line: &str, dst: &mut BytesMut) -> Result<(), CodecError> {
+        let length = line.len() + 2;
+        if length > self.max_line_length {
+            return Err(self.too_long(length));
+        }
+        if let Some(character) = line.chars().find(|ch| ['\r', '\n', '\0'].contains(ch)) {
+            return Err(CodecError::InvalidCharacter(character));
+        }
+        dst.reserve(length);
+        dst.put_slice(line.as_bytes());
+        dst.put_slice(b"\r\n");
+        Ok(())
+    }
+}
+
+impl Default for IrcCodec {
+    fn default() -> Self {
+        IrcCodec::new()
+    }
+}
+
+impl Decoder for IrcCodec {
+    type Item = Message;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            let newline = src[self.next_index..]
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .map(|offset| self.next_index + offset);
+            let end = match newline {
+                Some(end) => end,
+                None if self.discarding => {
+                    src.clear();
+                    self.next_index = 0;
+                    return Ok(None);
+                }
+                None if src.len() >= self.max_line_length => {
+                    let length = src.len();
+                    src.clear();
+                    self.next_index = 0;
+                    self.discarding = true;
+                    return Err(self.too_long(length));
+                }
+                None => {
+                    self.next_index = src.len();
+                    return Ok(None);
+                }
+            };
+
+            let line = src.split_to(end + 1);
+            self.next_index = 0;
+            if self.discarding {
+                self.discarding = false;
+                continue;
+            }
+            if line.len() > self.max_line_length {
+                return Err(self.too_long(line.len()));
+            }
+
+            let mut line = &line[..line.len() - 1];
+            if line.ends_with(b"\r") {
+                line = &line[..line.len() - 1];
+            }
+            if line.is_empty() {
+                continue;
+            }
+            let line = std::str::from_utf8(line).map_err(CodecError::InvalidUtf8)?;
+            return Ok(Some(Message::from(line)));
+        }
+    }
+}
+
+impl Encoder<Message> for IrcCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode_line(&item.to_string(), dst)
+    }
+}
+
+impl Encoder<&Message> for IrcCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: &Message, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode_line(&item.to_string(), dst)
+    }
+}
+
+impl Encoder<Builder> for IrcCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Builder, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode(item.build(), dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codec::IrcCodec;
+    use crate::errors::CodecError;
+    use crate::Message;
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    #[test]
+    fn test_decode_line_endings() -> Result<(), CodecError> {
+        let mut codec = IrcCodec::new();
+        let mut buf = BytesMut::from("CMD1\r\nCMD2\n\r\nCMD3 :trailing");
+        assert_eq!(Some(Message::from("CMD1")), codec.decode(&mut buf)?);
+        assert_eq!(Some(Message::from("CMD2")), codec.decode(&mut buf)?);
+        assert_eq!(None, codec.decode(&mut buf)?);
+        buf.extend_from_slice(b"\r\n");
+        assert_eq!(
+            Some(Message::from("CMD3 :trailing")),
+            codec.decode(&mut buf)?
+        );
+        assert!(buf.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_too_long() -> Result<(), CodecError> {
+        let mut codec = IrcCodec::with_max_line_length(8);
+        let mut buf = BytesMut::from("TOOLONGLINE");
+        match codec.decode(&mut buf) {
+            Err(CodecError::LineTooLong { length: 11, max: 8 }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        // The rest of the too long line is skipped
+        buf.extend_from_slice(b"REST\r\nCMD\r\n");
+        assert_eq!(Some(Message::from("CMD")), codec.decode(&mut buf)?);
+
+        let mut buf = BytesMut::from("TOOLONG\r\nCMD\r\n");
+        assert!(codec.decode(&mut buf).is_err());
+        assert_eq!(Some(Message::from("CMD")), codec.decode(&mut buf)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_invalid_utf8() {
+        let mut codec = IrcCodec::new();
+        let mut buf = BytesMut::from(&b"PRIVMSG #c :\xe4\r\n"[..]);
+        match codec.decode(&mut buf) {
+            Err(CodecError::InvalidUtf8(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_encode() -> Result<(), CodecError> {
+        let mut codec = IrcCodec::with_max_line_length(16);
+        let mut buf = BytesMut::new();
+        codec.encode(Message::from("CMD param"), &mut buf)?;
+        codec.encode(Message::builder("PONG").trailing("x"), &mut buf)?;
+        assert_eq!(&b"CMD param\r\nPONG :x\r\n"[..], &buf[..]);
+        assert!(codec
+            .encode(&Message::from("CMD with too long params"), &mut buf)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_invalid_character() {
+        let mut codec = IrcCodec::new();
+        let mut buf = BytesMut::new();
+        for (line, invalid) in &[
+            ("PRIVMSG #c :a\r\nQUIT", '\r'),
+            ("PRIVMSG #c :a\nQUIT", '\n'),
+            ("PRIVMSG #c :a\0b", '\0'),
+        ] {
+            match codec.encode(Message::from(*line), &mut buf) {
+                Err(CodecError::InvalidCharacter(character)) => assert_eq!(*invalid, character),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::numeric::Numeric;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+macro_rules! commands {
+    ($($(#[$meta:meta])* $variant:ident => $name:literal,)*) => {
+        /// Typed representation of the command of a message.
+        ///
+        /// Covers the commands of [RFC 2812](https://tools.ietf.org/html/rfc2812), the commonly
+        /// used [IRCv3](https://ircv3.net/irc/) commands and numeric replies. Commands not known
+        /// to this crate are returned as [Command::Unknown] referencing the original string.
+        ///
+        /// Parsing is case-insensitive as IRC commands are. As [Command::Unknown] holds the
+        /// original string two unknown commands only compare equal if they have the same case.
+        ///
+        /// # Usage
+        ///
+        /// ```rust
+        /// use irc_rust::command::Command;
+        /// use irc_rust::Message;
+        /// # fn main() -> Result<(), irc_rust::errors::ParserError> {
+        /// let message = Message::from(":nick!user@host privmsg #channel :Hello!");
+        /// assert_eq!(Command::Privmsg, message.typed_command()?);
+        ///
+        /// let message = Message::builder(Command::Join).param("#channel").build();
+        /// assert_eq!("JOIN #channel", message.to_string());
+        /// # Ok(())
+        /// # }
+        /// ```
+        #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+        pub enum Command<'a> {
+            $($(#[$meta])* $variant,)*
+            /// A three digit numeric reply like `001`.
+            Numeric(Numeric),
+            /// Any command not known to this crate.
+            Unknown(&'a str),
+        }
+
+        impl<'a> Command<'a> {
+            fn from_known(upper: &str) -> Option<Command<'static>> {
+                match upper {
+                    $($name => Some(Command::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl<'a> Display for Command<'a> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                match self {
+                    $(Command::$variant => f.write_str($name),)*
+                    Command::Numeric(numeric) => numeric.fmt(f),
+                    Command::Unknown(raw) => f.write_str(raw),
+                }
+            }
+        }
+    };
+}
+
+commands! {
+    // RFC 2812 - Connection registration
+    Pass => "PASS",
+    Nick => "NICK",
+    User => "USER",
+    Oper => "OPER",
+    Mode => "MODE",
+    Service => "SERVICE",
+    Quit => "QUIT",
+    Squit => "SQUIT",
+    // RFC 2812 - Channel operations
+    Join => "JOIN",
+    Part => "PART",
+    Topic => "TOPIC",
+    Names => "NAMES",
+    List => "LIST",
+    Invite => "INVITE",
+    Kick => "KICK",
+    // RFC 2812 - Sending messages
+    Privmsg => "PRIVMSG",
+    Notice => "NOTICE",
+    // RFC 2812 - Server queries and commands
+    Motd => "MOTD",
+    Lusers => "LUSERS",
+    Version => "VERSION",
+    Stats => "STATS",
+    Links => "LINKS",
+    Time => "TIME",
+    Connect => "CONNECT",
+    Trace => "TRACE",
+    Admin => "ADMIN",
+    Info => "INFO",
+    // RFC 2812 - Service query and commands
+    Servlist => "SERVLIST",
+    Squery => "SQUERY",
+    // RFC 2812 - User based queries
+    Who => "WHO",
+    Whois => "WHOIS",
+    Whowas => "WHOWAS",
+    // RFC 2812 - Miscellaneous messages
+    Kill => "KILL",
+    Ping => "PING",
+    Pong => "PONG",
+    Error => "ERROR",
+    // RFC 2812 - Optional features
+    Away => "AWAY",
+    Rehash => "REHASH",
+    Die => "DIE",
+    Restart => "RESTART",
+    Summon => "SUMMON",
+    Users => "USERS",
+    Wallops => "WALLOPS",
+    Userhost => "USERHOST",
+    Ison => "ISON",
+    // IRCv3
+    Cap => "CAP",
+    Authenticate => "AUTHENTICATE",
+    Account => "ACCOUNT",
+    Batch => "BATCH",
+    Chghost => "CHGHOST",
+    Setname => "SETNAME",
+    Tagmsg => "TAGMSG",
+    Monitor => "MONITOR",
+    Fail => "FAIL",
+    Warn => "WARN",
+    Note => "NOTE",
+    Webirc => "WEBIRC",
+}
+
+/// Length of the longest known command. Longer commands are always [Command::Unknown].
+const MAX_KNOWN_LEN: usize = 12;
+
+impl<'a> From<&'a str> for Command<'a> {
+    fn from(raw: &'a str) -> Self {
+        if let Some(numeric) = Numeric::parse(raw) {
+            return Command::Numeric(numeric);
+        }
+        let bytes = raw.as_bytes();
+        if bytes.len() > MAX_KNOWN_LEN || !raw.is_ascii() {
+            return Command::Unknown(raw);
+        }
+
+        let mut upper = [0u8; MAX_KNOWN_LEN];
+        let upper = &mut upper[..bytes.len()];
+        upper.copy_from_slice(bytes);
+        upper.make_ascii_uppercase();
+        std::str::from_utf8(upper)
+            .ok()
+            .and_then(Command::from_known)
+            .unwrap_or(Command::Unknown(raw))
+    }
+}
+
+impl<'a> Command<'a> {
+    /// Returns true if the command is a three digit numeric reply.
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Command::Numeric(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::Command;
+    use crate::numeric::Numeric;
+
+    #[test]
+    fn test_case_insensitive() {
+        assert_eq!(Command::Privmsg, Command::from("PRIVMSG"));
+        assert_eq!(Command::Privmsg, Command::from("privmsg"));
+        assert_eq!(Command::Authenticate, Command::from("Authenticate"));
+    }
+
+    #[test]
+    fn test_numeric() {
+        assert_eq!(Command::Numeric(Numeric::RPL_WELCOME), Command::from("001"));
+        assert_eq!(
+            Command::Numeric(Numeric::ERR_NICKNAMEINUSE),
+            Command::from("433")
+        );
+        assert_eq!("001", Command::Numeric(Numeric::RPL_WELCOME).to_string());
+        assert!(Command::from("353").is_numeric());
+        assert_eq!(Command::Unknown("0001"), Command::from("0001"));
+    }
+
+    #[test]
+    fn test_unknown() {
+        assert_eq!(Command::Unknown("FOO"), Command::from("FOO"));
+        assert_eq!(Command::Unknown("ÄWAY"), Command::from("ÄWAY"));
+        assert_eq!(
+            Command::Unknown("VERYLONGCOMMAND"),
+            Command::from("VERYLONGCOMMAND")
+        );
+        assert_eq!("foo", Command::from("foo").to_string());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("JOIN", Command::from("join").to_string());
+        assert_eq!("TAGMSG", Command::Tagmsg.to_string());
+    }
+}
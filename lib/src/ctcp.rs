@@ -0,0 +1,354 @@
+//! Client-To-Client Protocol messages embedded in `PRIVMSG` and `NOTICE` messages.
+//!
+//! A CTCP message is the trailing parameter wrapped in `\x01` like `\x01VERSION\x01`.
+//! Requests are sent as `PRIVMSG` and replies as `NOTICE`. Two levels of quoting exist:
+//!
+//! - The low-level quoting of the [original specification](https://www.irchelp.org/protocol/ctcpspec.html)
+//!   escapes NUL, CR, LF and `\x10` with `\x10` so they can be sent in a message.
+//! - The CTCP-level quoting escapes `\x01` and `\` with `\` inside of the params.
+//!
+//! [Ctcp] references the quoted params and [Ctcp::params_unquoted] removes both levels.
+//! The builders returned by [Builder::ctcp_request](crate::builder::Builder::ctcp_request) and
+//! [Builder::ctcp_reply](crate::builder::Builder::ctcp_reply) apply both.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::builder::Builder;
+//! use irc_rust::ctcp::Kind;
+//! use irc_rust::Message;
+//! # fn main() -> Result<(), irc_rust::errors::ParserError> {
+//! let message = Message::from(":nick!user@host PRIVMSG #channel :\x01ACTION waves\x01");
+//! let parsed = message.parse()?;
+//! let ctcp = parsed.ctcp().unwrap();
+//! assert_eq!(Kind::Request, ctcp.kind);
+//! assert!(ctcp.is("action"));
+//! assert_eq!(Some("waves"), ctcp.params);
+//!
+//! let reply = Builder::ctcp_reply("nick", "VERSION", Some("irc-rust 0.4")).build();
+//! assert_eq!("NOTICE nick :\x01VERSION irc-rust 0.4\x01", reply.to_string());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::command::Command;
+use std::borrow::Cow;
+use std::fmt;
+
+/// Delimiter wrapping CTCP messages.
+pub const DELIMITER: char = '\x01';
+/// Escape character of the low-level quoting.
+pub const LOW_LEVEL_QUOTE: char = '\x10';
+/// Escape character of the CTCP-level quoting.
+pub const CTCP_QUOTE: char = '\\';
+
+/// Whether a CTCP message is a request or a reply.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Kind {
+    /// Sent as `PRIVMSG`.
+    Request,
+    /// Sent as `NOTICE`.
+    Reply,
+}
+
+impl Kind {
+    /// Returns the kind of CTCP messages sent with the command.
+    pub fn from_command(command: Command<'_>) -> Option<Kind> {
+        match command {
+            Command::Privmsg => Some(Kind::Request),
+            Command::Notice => Some(Kind::Reply),
+            _ => None,
+        }
+    }
+
+    /// Returns the command used to send CTCP messages of this kind.
+    pub fn command(self) -> Command<'static> {
+        match self {
+            Kind::Request => Command::Privmsg,
+            Kind::Reply => Command::Notice,
+        }
+    }
+}
+
+/// A CTCP message like `ACTION`, `VERSION`, `PING`, `TIME`, `CLIENTINFO` or `DCC`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Ctcp<'a> {
+    pub kind: Kind,
+    /// The CTCP command as sent. Use [Ctcp::is] to compare case-insensitive.
+    pub command: &'a str,
+    /// Everything after the first space following the command still quoted.
+    pub params: Option<&'a str>,
+}
+
+impl<'a> Ctcp<'a> {
+    /// Parses the text of a `PRIVMSG` or `NOTICE` as CTCP message.
+    ///
+    /// Returns `None` if the text doesn't start with `\x01` or the command is empty. A
+    /// missing closing `\x01` is accepted as some clients omit it.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::ctcp::{Ctcp, Kind};
+    ///
+    /// let ctcp = Ctcp::parse(Kind::Request, "\x01PING 1612345678\x01").unwrap();
+    /// assert_eq!("PING", ctcp.command);
+    /// assert_eq!(Some("1612345678"), ctcp.params);
+    /// assert_eq!(None, Ctcp::parse(Kind::Request, "Hello"));
+    /// ```
+    pub fn parse(kind: Kind, text: &'a str) -> Option<Ctcp<'a>> {
+        let text = text.strip_prefix(DELIMITER)?;
+        let text = match text.find(DELIMITER) {
+            Some(end) => &text[..end],
+            None => text,
+        };
+        let (command, params) = match text.find(' ') {
+            Some(space) => (&text[..space], Some(&text[space + 1..])),
+            None => (text, None),
+        };
+        if command.is_empty() {
+            return None;
+        }
+        Some(Ctcp {
+            kind,
+            command,
+            params,
+        })
+    }
+
+    /// Returns true if the command equals **command** ignoring ASCII case.
+    pub fn is(&self, command: &str) -> bool {
+        self.command.eq_ignore_ascii_case(command)
+    }
+
+    /// Returns the params with the low-level and CTCP-level quoting removed.
+    ///
+    /// Returns the params borrowed if they contain nothing quoted.
+    pub fn params_unquoted(&self) -> Option<Cow<'a, str>> {
+        self.params.map(|params| match low_level_dequote(params) {
+            Cow::Borrowed(params) => dequote(params),
+            Cow::Owned(params) => Cow::Owned(dequote(&params).into_owned()),
+        })
+    }
+
+    /// Returns an iterator over the space separated arguments of the still quoted params.
+    pub fn args(&self) -> impl Iterator<Item = &'a str> {
+        self.params
+            .unwrap_or_default()
+            .split(' ')
+            .filter(|arg| !arg.is_empty())
+    }
+}
+
+impl<'a> fmt::Display for Ctcp<'a> {
+    /// Writes the CTCP message including the delimiters as used as trailing parameter.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.params {
+            Some(params) => write!(f, "{}{} {}{}", DELIMITER, self.command, params, DELIMITER),
+            None => write!(f, "{}{}{}", DELIMITER, self.command, DELIMITER),
+        }
+    }
+}
+
+/// Applies the low-level quoting escaping NUL, CR, LF and `\x10`.
+pub fn low_level_quote(text: &str) -> Cow<'_, str> {
+    if !text.contains(&['\0', '\r', '\n', LOW_LEVEL_QUOTE][..]) {
+        return Cow::Borrowed(text);
+    }
+    let mut quoted = String::with_capacity(text.len() + 4);
+    for ch in text.chars() {
+        match ch {
+            '\0' => quoted.push_str("\x100"),
+            '\r' => quoted.push_str("\x10r"),
+            '\n' => quoted.push_str("\x10n"),
+            LOW_LEVEL_QUOTE => quoted.push_str("\x10\x10"),
+            _ => quoted.push(ch),
+        }
+    }
+    Cow::Owned(quoted)
+}
+
+/// Removes the low-level quoting. Unknown escapes result in the escaped character.
+pub fn low_level_dequote(text: &str) -> Cow<'_, str> {
+    unescape(text, LOW_LEVEL_QUOTE, |ch| match ch {
+        '0' => '\0',
+        'r' => '\r',
+        'n' => '\n',
+        _ => ch,
+    })
+}
+
+/// Applies the CTCP-level quoting escaping `\x01` and `\`.
+pub fn quote(text: &str) -> Cow<'_, str> {
+    if !text.contains(&[DELIMITER, CTCP_QUOTE][..]) {
+        return Cow::Borrowed(text);
+    }
+    let mut quoted = String::with_capacity(text.len() + 4);
+    for ch in text.chars() {
+        match ch {
+            DELIMITER => quoted.push_str("\\a"),
+            CTCP_QUOTE => quoted.push_str("\\\\"),
+            _ => quoted.push(ch),
+        }
+    }
+    Cow::Owned(quoted)
+}
+
+/// Removes the CTCP-level quoting. Unknown escapes result in the escaped character.
+pub fn dequote(text: &str) -> Cow<'_, str> {
+    unescape(text, CTCP_QUOTE, |ch| match ch {
+        'a' => DELIMITER,
+        _ => ch,
+    })
+}
+
+fn unescape(text: &str, escape: char, map: impl Fn(char) -> char) -> Cow<'_, str> {
+    if !text.contains(escape) {
+        return Cow::Borrowed(text);
+    }
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch != escape {
+            unescaped.push(ch);
+        } else if let Some(escaped) = chars.next() {
+            unescaped.push(map(escaped));
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// Returns the trailing parameter for a CTCP message with both levels of quoting applied.
+pub(crate) fn encode(command: &str, params: Option<&str>) -> String {
+    let text = match params {
+        Some(params) => format!("{}{} {}{}", DELIMITER, command, quote(params), DELIMITER),
+        None => format!("{}{}{}", DELIMITER, command, DELIMITER),
+    };
+    low_level_quote(&text).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::Builder;
+    use crate::ctcp::{dequote, low_level_dequote, low_level_quote, quote, Ctcp, Kind};
+    use crate::errors::BuildError;
+    use crate::Message;
+    use std::borrow::Cow;
+    use std::error::Error;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Some(Ctcp {
+                kind: Kind::Request,
+                command: "VERSION",
+                params: None,
+            }),
+            Ctcp::parse(Kind::Request, "\x01VERSION\x01")
+        );
+        assert_eq!(
+            Some(Ctcp {
+                kind: Kind::Reply,
+                command: "TIME",
+                params: Some("Thu Feb  4 12:00:00 2021"),
+            }),
+            Ctcp::parse(Kind::Reply, "\x01TIME Thu Feb  4 12:00:00 2021\x01")
+        );
+        // Missing closing delimiter
+        assert_eq!(
+            Some("waves"),
+            Ctcp::parse(Kind::Request, "\x01ACTION waves").and_then(|ctcp| ctcp.params)
+        );
+        assert_eq!(None, Ctcp::parse(Kind::Request, "\x01\x01"));
+        assert_eq!(None, Ctcp::parse(Kind::Request, "\x01 params\x01"));
+        assert_eq!(None, Ctcp::parse(Kind::Request, "text \x01VERSION\x01"));
+    }
+
+    #[test]
+    fn test_args() {
+        let ctcp =
+            Ctcp::parse(Kind::Request, "\x01DCC SEND file 3232235777 4000 1024\x01").unwrap();
+        assert!(ctcp.is("dcc"));
+        assert_eq!(
+            vec!["SEND", "file", "3232235777", "4000", "1024"],
+            ctcp.args().collect::<Vec<_>>()
+        );
+        let ctcp = Ctcp::parse(Kind::Request, "\x01CLIENTINFO\x01").unwrap();
+        assert_eq!(0, ctcp.args().count());
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert!(matches!(low_level_quote("plain"), Cow::Borrowed("plain")));
+        assert_eq!(
+            "a\x100b\x10rc\x10nd\x10\x10",
+            low_level_quote("a\0b\rc\nd\x10")
+        );
+        assert_eq!(
+            "a\0b\rc\nd\x10x",
+            low_level_dequote("a\x100b\x10rc\x10nd\x10\x10\x10x")
+        );
+        assert!(matches!(quote("plain"), Cow::Borrowed("plain")));
+        assert_eq!("\\a\\\\", quote("\x01\\"));
+        assert_eq!("\x01\\x", dequote("\\a\\\\\\x"));
+    }
+
+    #[test]
+    fn test_params_unquoted() {
+        let ctcp = Ctcp::parse(Kind::Request, "\x01PING plain\x01").unwrap();
+        assert!(matches!(
+            ctcp.params_unquoted(),
+            Some(Cow::Borrowed("plain"))
+        ));
+        let ctcp = Ctcp::parse(Kind::Request, "\x01ECHO a\\ab\\\\c\x10nd\x01").unwrap();
+        assert_eq!(Some("a\x01b\\c\nd"), ctcp.params_unquoted().as_deref());
+    }
+
+    #[test]
+    fn test_builder() -> Result<(), Box<dyn Error>> {
+        let message = Builder::ctcp_request("#channel", "ACTION", Some("waves")).build();
+        assert_eq!(
+            "PRIVMSG #channel :\x01ACTION waves\x01",
+            message.to_string()
+        );
+        let message = Builder::ctcp_request("nick", "VERSION", None).build();
+        assert_eq!("PRIVMSG nick :\x01VERSION\x01", message.to_string());
+
+        let message = Builder::ctcp_reply("nick", "ECHO", Some("a\x01b\\c\r\nd")).try_build()?;
+        assert_eq!(
+            "NOTICE nick :\x01ECHO a\\ab\\\\c\x10r\x10nd\x01",
+            message.to_string()
+        );
+        let parsed = message.parse()?;
+        let ctcp = parsed.ctcp().unwrap();
+        assert_eq!(Kind::Reply, ctcp.kind);
+        assert_eq!(Some("a\x01b\\c\r\nd"), ctcp.params_unquoted().as_deref());
+
+        assert_eq!(
+            Err(BuildError::InvalidCharacter {
+                part: "param",
+                character: ' '
+            }),
+            Builder::try_ctcp_request("a b", "VERSION", None)
+        );
+        assert_eq!(
+            Err(BuildError::EmptyParam),
+            Builder::try_ctcp_reply("", "VERSION", None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parsed() -> Result<(), Box<dyn Error>> {
+        let message = Message::from("PRIVMSG #channel :Hello");
+        assert_eq!(None, message.parse()?.ctcp());
+        let message = Message::from("TOPIC #channel :\x01VERSION\x01");
+        assert_eq!(None, message.parse()?.ctcp());
+        let message = Message::from("NOTICE nick :\x01PING 123\x01");
+        assert_eq!(
+            "\x01PING 123\x01",
+            message.parse()?.ctcp().unwrap().to_string()
+        );
+        Ok(())
+    }
+}
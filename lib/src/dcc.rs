@@ -0,0 +1,486 @@
+//! Direct Client-to-Client offers sent as CTCP `DCC` requests.
+//!
+//! Supports the `SEND`, `CHAT`, `RESUME` and `ACCEPT` types. IPv4 addresses are sent as
+//! integer and IPv6 addresses in their textual form. A passive (reverse) DCC is offered
+//! with port `0` and a token. The receiver then listens and replies with the same offer
+//! containing its address, port and the token.
+//!
+//! With the `dcc` feature the `transfer` module performs the transfers over TCP.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::dcc::Dcc;
+//! use irc_rust::Message;
+//! use std::convert::TryFrom;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let message = Message::from(":bot!bot@host PRIVMSG nick :\x01DCC SEND \"my file.txt\" 3232235777 5000 1024\x01");
+//! let parsed = message.parse()?;
+//! let offer = Dcc::try_from(parsed.ctcp().unwrap())?;
+//! assert_eq!(Some("192.168.1.1:5000".parse()?), offer.socket_addr());
+//!
+//! // Continue a partial download
+//! let resume = offer.resume(512).unwrap();
+//! assert_eq!(
+//!     "PRIVMSG bot :\x01DCC RESUME \"my file.txt\" 5000 512\x01",
+//!     resume.request("bot").build().to_string()
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::builder::Builder;
+use crate::ctcp::Ctcp;
+use crate::errors::DccError;
+use std::convert::TryFrom;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+
+#[cfg(feature = "dcc")]
+pub mod transfer;
+
+/// A DCC offer or negotiation message.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Dcc<'a> {
+    /// `DCC SEND <filename> <address> <port> [<size> [<token>]]` offers a file.
+    Send {
+        filename: &'a str,
+        address: IpAddr,
+        port: u16,
+        size: Option<u64>,
+        token: Option<&'a str>,
+    },
+    /// `DCC CHAT chat <address> <port> [<token>]` offers a chat.
+    Chat {
+        address: IpAddr,
+        port: u16,
+        token: Option<&'a str>,
+    },
+    /// `DCC RESUME <filename> <port> <position> [<token>]` asks the sender to continue at
+    /// **position**.
+    Resume {
+        filename: &'a str,
+        port: u16,
+        position: u64,
+        token: Option<&'a str>,
+    },
+    /// `DCC ACCEPT <filename> <port> <position> [<token>]` accepts a [Dcc::Resume].
+    Accept {
+        filename: &'a str,
+        port: u16,
+        position: u64,
+        token: Option<&'a str>,
+    },
+}
+
+impl<'a> Dcc<'a> {
+    /// Returns the port of the offer. Is `0` for passive offers.
+    pub fn port(&self) -> u16 {
+        match self {
+            Dcc::Send { port, .. }
+            | Dcc::Chat { port, .. }
+            | Dcc::Resume { port, .. }
+            | Dcc::Accept { port, .. } => *port,
+        }
+    }
+
+    pub fn token(&self) -> Option<&'a str> {
+        match self {
+            Dcc::Send { token, .. }
+            | Dcc::Chat { token, .. }
+            | Dcc::Resume { token, .. }
+            | Dcc::Accept { token, .. } => *token,
+        }
+    }
+
+    /// Returns true if the offer is passive and the receiver has to listen.
+    pub fn is_passive(&self) -> bool {
+        self.port() == 0 && self.token().is_some()
+    }
+
+    /// Returns the address to connect to for `SEND` and `CHAT` offers which aren't passive.
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Dcc::Send { address, port, .. } | Dcc::Chat { address, port, .. } if *port != 0 => {
+                Some(SocketAddr::new(*address, *port))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the [Dcc::Resume] continuing a `SEND` offer at **position**.
+    pub fn resume(&self, position: u64) -> Option<Dcc<'a>> {
+        match *self {
+            Dcc::Send {
+                filename,
+                port,
+                token,
+                ..
+            } => Some(Dcc::Resume {
+                filename,
+                port,
+                position,
+                token,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the [Dcc::Accept] for a [Dcc::Resume].
+    pub fn accept(&self) -> Option<Dcc<'a>> {
+        match *self {
+            Dcc::Resume {
+                filename,
+                port,
+                position,
+                token,
+            } => Some(Dcc::Accept {
+                filename,
+                port,
+                position,
+                token,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the reply to a passive offer with the address and port the receiver listens
+    /// on.
+    pub fn passive_reply(&self, address: IpAddr, port: u16) -> Option<Dcc<'a>> {
+        if !self.is_passive() {
+            return None;
+        }
+        match *self {
+            Dcc::Send {
+                filename,
+                size,
+                token,
+                ..
+            } => Some(Dcc::Send {
+                filename,
+                address,
+                port,
+                size,
+                token,
+            }),
+            Dcc::Chat { token, .. } => Some(Dcc::Chat {
+                address,
+                port,
+                token,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Creates a builder for the CTCP request sending this offer to **target**.
+    ///
+    /// # Panics
+    ///
+    /// Panics if **target** isn't a valid param, see [Builder::try_ctcp_request].
+    pub fn request<S: ToString>(&self, target: S) -> Builder {
+        Builder::ctcp_request(target, "DCC", Some(&self.to_string()))
+    }
+}
+
+impl<'a> fmt::Display for Dcc<'a> {
+    /// Writes the params of the CTCP `DCC` message.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dcc::Send {
+                filename,
+                address,
+                port,
+                size,
+                token,
+            } => {
+                write!(
+                    f,
+                    "SEND {} {} {}",
+                    Filename(filename),
+                    Address(*address),
+                    port
+                )?;
+                match (size, token) {
+                    (Some(size), Some(token)) => write!(f, " {} {}", size, token),
+                    (Some(size), None) => write!(f, " {}", size),
+                    // The size is required before a token
+                    (None, Some(token)) => write!(f, " 0 {}", token),
+                    (None, None) => Ok(()),
+                }
+            }
+            Dcc::Chat {
+                address,
+                port,
+                token,
+            } => {
+                write!(f, "CHAT chat {} {}", Address(*address), port)?;
+                write_token(f, *token)
+            }
+            Dcc::Resume {
+                filename,
+                port,
+                position,
+                token,
+            } => {
+                write!(f, "RESUME {} {} {}", Filename(filename), port, position)?;
+                write_token(f, *token)
+            }
+            Dcc::Accept {
+                filename,
+                port,
+                position,
+                token,
+            } => {
+                write!(f, "ACCEPT {} {} {}", Filename(filename), port, position)?;
+                write_token(f, *token)
+            }
+        }
+    }
+}
+
+fn write_token(f: &mut fmt::Formatter<'_>, token: Option<&str>) -> fmt::Result {
+    match token {
+        Some(token) => write!(f, " {}", token),
+        None => Ok(()),
+    }
+}
+
+/// Filenames containing spaces are quoted.
+struct Filename<'a>(&'a str);
+
+impl<'a> fmt::Display for Filename<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.contains(' ') {
+            write!(f, "\"{}\"", self.0)
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
+
+/// IPv4 addresses are written as integer.
+struct Address(IpAddr);
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            IpAddr::V4(address) => write!(f, "{}", u32::from(address)),
+            IpAddr::V6(address) => write!(f, "{}", address),
+        }
+    }
+}
+
+/// Splits the params into arguments. Arguments in double quotes may contain spaces.
+fn split_args(params: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let mut rest = params.trim_start_matches(' ');
+    while !rest.is_empty() {
+        let (arg, next) = match rest.strip_prefix('"').and_then(|quoted| {
+            quoted
+                .find('"')
+                .map(|end| (&quoted[..end], &quoted[end + 1..]))
+        }) {
+            Some(quoted) => quoted,
+            None => match rest.find(' ') {
+                Some(end) => (&rest[..end], &rest[end..]),
+                None => (rest, ""),
+            },
+        };
+        args.push(arg);
+        rest = next.trim_start_matches(' ');
+    }
+    args
+}
+
+/// Parses an IPv4 address as integer or any IP address in its textual form.
+fn parse_address(address: &str) -> Result<IpAddr, DccError> {
+    if address.bytes().all(|byte| byte.is_ascii_digit()) {
+        u32::from_str(address)
+            .map(|address| IpAddr::V4(Ipv4Addr::from(address)))
+            .map_err(|_| DccError::InvalidAddress(address.to_string()))
+    } else {
+        IpAddr::from_str(address).map_err(|_| DccError::InvalidAddress(address.to_string()))
+    }
+}
+
+fn parse_number<T: FromStr>(name: &'static str, value: &str) -> Result<T, DccError> {
+    T::from_str(value).map_err(|_| DccError::InvalidNumber {
+        name,
+        value: value.to_string(),
+    })
+}
+
+impl<'a> TryFrom<Ctcp<'a>> for Dcc<'a> {
+    type Error = DccError;
+
+    fn try_from(ctcp: Ctcp<'a>) -> Result<Self, Self::Error> {
+        if !ctcp.is("DCC") {
+            return Err(DccError::NotDcc);
+        }
+        let args = split_args(ctcp.params.unwrap_or_default());
+        let arg = |index: usize, name: &'static str| {
+            args.get(index)
+                .copied()
+                .ok_or(DccError::MissingArgument(name))
+        };
+        let kind = arg(0, "type")?;
+        let dcc = if kind.eq_ignore_ascii_case("SEND") {
+            Dcc::Send {
+                filename: arg(1, "filename")?,
+                address: parse_address(arg(2, "address")?)?,
+                port: parse_number("port", arg(3, "port")?)?,
+                size: args
+                    .get(4)
+                    .map(|size| parse_number("size", size))
+                    .transpose()?,
+                token: args.get(5).copied(),
+            }
+        } else if kind.eq_ignore_ascii_case("CHAT") {
+            Dcc::Chat {
+                address: parse_address(arg(2, "address")?)?,
+                port: parse_number("port", arg(3, "port")?)?,
+                token: args.get(4).copied(),
+            }
+        } else if kind.eq_ignore_ascii_case("RESUME") || kind.eq_ignore_ascii_case("ACCEPT") {
+            let filename = arg(1, "filename")?;
+            let port = parse_number("port", arg(2, "port")?)?;
+            let position = parse_number("position", arg(3, "position")?)?;
+            let token = args.get(4).copied();
+            if kind.eq_ignore_ascii_case("RESUME") {
+                Dcc::Resume {
+                    filename,
+                    port,
+                    position,
+                    token,
+                }
+            } else {
+                Dcc::Accept {
+                    filename,
+                    port,
+                    position,
+                    token,
+                }
+            }
+        } else {
+            return Err(DccError::UnknownType(kind.to_string()));
+        };
+        Ok(dcc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctcp::{Ctcp, Kind};
+    use crate::dcc::{split_args, Dcc};
+    use crate::errors::DccError;
+    use std::convert::TryFrom;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    fn parse(text: &str) -> Result<Dcc<'_>, DccError> {
+        Dcc::try_from(Ctcp::parse(Kind::Request, text).unwrap())
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            vec!["SEND", "my file", "1", "2"],
+            split_args("SEND \"my file\" 1  2")
+        );
+        assert_eq!(vec!["SEND", "\"open"], split_args("SEND \"open"));
+        assert!(split_args("").is_empty());
+    }
+
+    #[test]
+    fn test_send() {
+        assert_eq!(
+            Ok(Dcc::Send {
+                filename: "file.txt",
+                address: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+                port: 5000,
+                size: Some(1024),
+                token: None,
+            }),
+            parse("\x01DCC SEND file.txt 3232235777 5000 1024\x01")
+        );
+        assert_eq!(
+            Ok(Dcc::Send {
+                filename: "file.txt",
+                address: IpAddr::V6(Ipv6Addr::LOCALHOST),
+                port: 5000,
+                size: None,
+                token: None,
+            }),
+            parse("\x01DCC SEND file.txt ::1 5000\x01")
+        );
+    }
+
+    #[test]
+    fn test_passive() {
+        let offer = parse("\x01DCC SEND file.txt 3232235777 0 1024 42\x01").unwrap();
+        assert!(offer.is_passive());
+        assert_eq!(None, offer.socket_addr());
+        let reply = offer
+            .passive_reply(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 6000)
+            .unwrap();
+        assert_eq!("SEND file.txt 167772161 6000 1024 42", reply.to_string());
+        assert!(!reply.is_passive());
+        assert_eq!(
+            None,
+            reply.passive_reply(IpAddr::V6(Ipv6Addr::LOCALHOST), 1)
+        );
+
+        let offer = parse("\x01DCC CHAT chat 2130706433 0 7\x01").unwrap();
+        assert!(offer.is_passive());
+        assert_eq!(Some("7"), offer.token());
+    }
+
+    #[test]
+    fn test_chat() {
+        let offer = parse("\x01DCC CHAT chat 2130706433 4000\x01").unwrap();
+        assert_eq!(Some("127.0.0.1:4000".parse().unwrap()), offer.socket_addr());
+        assert_eq!("CHAT chat 2130706433 4000", offer.to_string());
+    }
+
+    #[test]
+    fn test_resume_accept() {
+        let offer = parse("\x01DCC SEND \"my file\" 2130706433 4000 100\x01").unwrap();
+        let resume = offer.resume(50).unwrap();
+        assert_eq!("RESUME \"my file\" 4000 50", resume.to_string());
+        assert_eq!(Ok(resume), parse("\x01DCC RESUME \"my file\" 4000 50\x01"));
+        let accept = resume.accept().unwrap();
+        assert_eq!("ACCEPT \"my file\" 4000 50", accept.to_string());
+        assert_eq!(
+            "NOTICE nick :\x01DCC ACCEPT \"my file\" 4000 50\x01",
+            crate::builder::Builder::ctcp_reply("nick", "DCC", Some(&accept.to_string()))
+                .build()
+                .to_string()
+        );
+        assert_eq!(None, accept.accept());
+        assert_eq!(None, accept.resume(0));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Err(DccError::NotDcc), parse("\x01VERSION\x01"));
+        assert_eq!(
+            Err(DccError::UnknownType("XMIT".to_string())),
+            parse("\x01DCC XMIT file\x01")
+        );
+        assert_eq!(
+            Err(DccError::MissingArgument("port")),
+            parse("\x01DCC SEND file 1\x01")
+        );
+        assert_eq!(
+            Err(DccError::InvalidAddress("99999999999".to_string())),
+            parse("\x01DCC SEND file 99999999999 1\x01")
+        );
+        assert_eq!(
+            Err(DccError::InvalidNumber {
+                name: "port",
+                value: "70000".to_string()
+            }),
+            parse("\x01DCC CHAT chat ::1 70000\x01")
+        );
+    }
+}
@@ -0,0 +1,331 @@
+//! Blocking DCC `SEND` and `CHAT` transfers.
+//!
+//! Requires the `dcc` feature.
+//!
+//! A file is sent as raw bytes. The receiver acknowledges received data by sending the
+//! total position in the file as 4 byte big endian integer. For files larger than 4 GiB the
+//! position wraps. [Transfer::send] waits for acknowledgements if more than
+//! [Transfer::window] bytes are unacknowledged and for the final acknowledgement. Both sides
+//! start at an offset to support resuming transfers negotiated by
+//! [Dcc::Resume](crate::dcc::Dcc::Resume) and [Dcc::Accept](crate::dcc::Dcc::Accept).
+//!
+//! All functions work on any [Read] + [Write] stream like a [TcpStream].
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::dcc::transfer::{listen, Transfer};
+//! use std::io::Cursor;
+//! use std::net::{IpAddr, Ipv4Addr, TcpStream};
+//! use std::thread;
+//! # fn main() -> std::io::Result<()> {
+//! let data = b"file content".to_vec();
+//! let (listener, port) = listen(IpAddr::V4(Ipv4Addr::LOCALHOST))?;
+//! let sender = thread::spawn(move || {
+//!     // The offer is sent with the port and the sender accepts the connection
+//!     let (mut stream, _) = listener.accept()?;
+//!     Transfer::default().send(&mut stream, &mut Cursor::new(data), 0)
+//! });
+//!
+//! let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))?;
+//! let mut file = Vec::new();
+//! Transfer::default().receive(&mut stream, &mut file, Some(12), 0)?;
+//! assert_eq!(b"file content", &file[..]);
+//! assert_eq!(12, sender.join().unwrap()?);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener};
+
+/// Default size of the blocks read and written.
+pub const DEFAULT_BLOCK_SIZE: usize = 8192;
+/// Default number of unacknowledged bytes after which the sender waits for
+/// acknowledgements.
+pub const DEFAULT_WINDOW: u64 = 64 * DEFAULT_BLOCK_SIZE as u64;
+
+/// Binds a listener on **address** with a port chosen by the system.
+///
+/// Returns the listener and its port to use in the offer.
+pub fn listen(address: IpAddr) -> io::Result<(TcpListener, u16)> {
+    let listener = TcpListener::bind(SocketAddr::new(address, 0))?;
+    let port = listener.local_addr()?.port();
+    Ok((listener, port))
+}
+
+/// Configuration of a file transfer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Transfer {
+    pub block_size: usize,
+    /// Maximum number of bytes sent without being acknowledged.
+    pub window: u64,
+}
+
+impl Default for Transfer {
+    fn default() -> Self {
+        Transfer {
+            block_size: DEFAULT_BLOCK_SIZE,
+            window: DEFAULT_WINDOW,
+        }
+    }
+}
+
+impl Transfer {
+    /// Sends **file** starting at **offset** and waits until everything is acknowledged.
+    ///
+    /// Returns the number of bytes sent. Closing the connection after all bytes were sent
+    /// counts as acknowledgement as some clients don't send the last one.
+    pub fn send<S, F>(&self, stream: &mut S, file: &mut F, offset: u64) -> io::Result<u64>
+    where
+        S: Read + Write,
+        F: Read + Seek,
+    {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut buffer = vec![0; self.block_size.max(1)];
+        let mut position = offset;
+        let mut acknowledged = offset;
+        loop {
+            let read = match file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(why) if why.kind() == ErrorKind::Interrupted => continue,
+                Err(why) => return Err(why),
+            };
+            stream.write_all(&buffer[..read])?;
+            position += read as u64;
+            while position - acknowledged > self.window {
+                match read_ack(stream, position)? {
+                    Some(ack) => acknowledged = ack,
+                    None => return Err(ErrorKind::UnexpectedEof.into()),
+                }
+            }
+        }
+        stream.flush()?;
+        while acknowledged < position {
+            match read_ack(stream, position)? {
+                Some(ack) => acknowledged = ack,
+                None => break,
+            }
+        }
+        Ok(position - offset)
+    }
+
+    /// Receives a file into **file** which already contains **offset** bytes and
+    /// acknowledges every received block.
+    ///
+    /// Stops if **size** bytes of the file are present or the sender closes the connection.
+    /// Returns the number of bytes received. Fails with [ErrorKind::UnexpectedEof] if the
+    /// connection is closed before **size** is reached.
+    pub fn receive<S, W>(
+        &self,
+        stream: &mut S,
+        file: &mut W,
+        size: Option<u64>,
+        offset: u64,
+    ) -> io::Result<u64>
+    where
+        S: Read + Write,
+        W: Write,
+    {
+        let mut buffer = vec![0; self.block_size.max(1)];
+        let mut position = offset;
+        while size.is_none_or(|size| position < size) {
+            let read = match stream.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(why) if why.kind() == ErrorKind::Interrupted => continue,
+                Err(why) => return Err(why),
+            };
+            file.write_all(&buffer[..read])?;
+            position += read as u64;
+            // Positions above 4 GiB wrap
+            stream.write_all(&(position as u32).to_be_bytes())?;
+        }
+        file.flush()?;
+        stream.flush()?;
+        if size.is_some_and(|size| position < size) {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        Ok(position - offset)
+    }
+}
+
+/// Reads an acknowledgement and returns the position it acknowledges.
+///
+/// Returns `None` if the connection was closed. The position is the highest position not
+/// above **position** matching the wrapped 4 byte value.
+fn read_ack<S: Read>(stream: &mut S, position: u64) -> io::Result<Option<u64>> {
+    let mut ack = [0; 4];
+    match stream.read_exact(&mut ack) {
+        Ok(()) => {}
+        Err(why) if why.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(why) => return Err(why),
+    }
+    let candidate = (position & !0xFFFF_FFFF) | u64::from(u32::from_be_bytes(ack));
+    if candidate <= position {
+        Ok(Some(candidate))
+    } else if candidate >= 1 << 32 {
+        Ok(Some(candidate - (1 << 32)))
+    } else {
+        Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "acknowledged more bytes than sent",
+        ))
+    }
+}
+
+/// A line-based DCC `CHAT` connection.
+#[derive(Debug)]
+pub struct Chat<S: Read + Write> {
+    stream: BufReader<S>,
+}
+
+impl<S: Read + Write> Chat<S> {
+    pub fn new(stream: S) -> Self {
+        Chat {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    /// Sends a line terminated with LF.
+    pub fn send_line(&mut self, line: &str) -> io::Result<()> {
+        let stream = self.stream.get_mut();
+        stream.write_all(line.as_bytes())?;
+        stream.write_all(b"\n")?;
+        stream.flush()
+    }
+
+    /// Reads the next line without its line ending. Returns `None` if the connection was
+    /// closed.
+    pub fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let len = line.trim_end_matches(&['\r', '\n'][..]).len();
+        line.truncate(len);
+        Ok(Some(line))
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dcc::transfer::{listen, read_ack, Chat, Transfer};
+    use std::io;
+    use std::io::{Cursor, ErrorKind, Read, Write};
+    use std::net::{IpAddr, Ipv4Addr, TcpStream};
+    use std::thread;
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|index| (index % 251) as u8).collect()
+    }
+
+    fn loopback() -> io::Result<(TcpStream, TcpStream)> {
+        let (listener, port) = listen(IpAddr::V4(Ipv4Addr::LOCALHOST))?;
+        let client = TcpStream::connect((Ipv4Addr::LOCALHOST, port))?;
+        let (server, _) = listener.accept()?;
+        Ok((server, client))
+    }
+
+    #[test]
+    fn test_transfer() -> io::Result<()> {
+        let (mut server, mut client) = loopback()?;
+        let file = data(100_000);
+        let expected = file.clone();
+        let transfer = Transfer {
+            block_size: 1000,
+            window: 4000,
+        };
+        let sender = thread::spawn(move || transfer.send(&mut server, &mut Cursor::new(file), 0));
+        let mut received = Vec::new();
+        assert_eq!(
+            100_000,
+            transfer.receive(&mut client, &mut received, Some(100_000), 0)?
+        );
+        assert_eq!(100_000, sender.join().unwrap()?);
+        assert_eq!(expected, received);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume() -> io::Result<()> {
+        let (mut server, mut client) = loopback()?;
+        let file = data(50_000);
+        let mut received = file[..30_000].to_vec();
+        let expected = file.clone();
+        let sender = thread::spawn(move || {
+            Transfer::default().send(&mut server, &mut Cursor::new(file), 30_000)
+        });
+        assert_eq!(
+            20_000,
+            Transfer::default().receive(&mut client, &mut received, Some(50_000), 30_000)?
+        );
+        assert_eq!(20_000, sender.join().unwrap()?);
+        assert_eq!(expected, received);
+        Ok(())
+    }
+
+    #[test]
+    fn test_acks() -> io::Result<()> {
+        let (mut server, mut client) = loopback()?;
+        let receiver = thread::spawn(move || {
+            let mut received = Vec::new();
+            Transfer::default()
+                .receive(&mut client, &mut received, Some(110), 100)
+                .map(|_| received)
+        });
+        server.write_all(b"12345")?;
+        let mut ack = [0; 4];
+        server.read_exact(&mut ack)?;
+        assert_eq!(105u32.to_be_bytes(), ack);
+        server.write_all(b"67890")?;
+        server.read_exact(&mut ack)?;
+        assert_eq!(110u32.to_be_bytes(), ack);
+        assert_eq!(b"1234567890", &receiver.join().unwrap()?[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_closed_early() -> io::Result<()> {
+        let (mut server, mut client) = loopback()?;
+        server.write_all(b"123")?;
+        drop(server);
+        let why = Transfer::default()
+            .receive(&mut client, &mut Vec::new(), Some(10), 0)
+            .unwrap_err();
+        assert_eq!(ErrorKind::UnexpectedEof, why.kind());
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_ack() -> io::Result<()> {
+        let ack = |value: u32, position: u64| read_ack(&mut &value.to_be_bytes()[..], position);
+        assert_eq!(Some(10), ack(10, 20)?);
+        // Wrapped positions above 4 GiB
+        assert_eq!(Some((1 << 32) + 10), ack(10, (1 << 32) + 20)?);
+        assert_eq!(Some(u64::from(u32::MAX)), ack(u32::MAX, (1 << 32) + 20)?);
+        assert_eq!(ErrorKind::InvalidData, ack(30, 20).unwrap_err().kind());
+        assert_eq!(None, read_ack(&mut &[0u8; 2][..], 20)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chat() -> io::Result<()> {
+        let (server, client) = loopback()?;
+        let mut server = Chat::new(server);
+        let mut client = Chat::new(client);
+        server.send_line("Hello")?;
+        client.send_line("Hi\r")?;
+        assert_eq!(Some("Hello".to_string()), client.read_line()?);
+        assert_eq!(Some("Hi".to_string()), server.read_line()?);
+        drop(client);
+        assert_eq!(None, server.read_line()?);
+        Ok(())
+    }
+}
@@ -1,22 +1,362 @@
 use std::error::Error;
+use std::fmt;
+use std::ops::Range;
 
-#[derive(Debug, Eq, PartialEq)]
-pub enum ParserError {
+/// The part of a message an error concerns.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Component {
+    Tags,
+    Prefix,
+    Command,
+    Params,
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Component::Tags => write!(f, "tags"),
+            Component::Prefix => write!(f, "prefix"),
+            Component::Command => write!(f, "command"),
+            Component::Params => write!(f, "params"),
+        }
+    }
+}
+
+/// The kind of a [ParserError].
+///
+/// The [Tokenizer](crate::tokenizer::Tokenizer) is lenient and only returns the first four
+/// kinds. The others are reported by the strict [validate](crate::validate) mode.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ParserErrorKind {
     NoTagKeyEnd,
     NoTagValueEnd,
     NoCommand,
     PrefixWithoutName,
+    EmptyTagKey,
+    /// A tag key contains characters other than letters, digits and `-` or has an invalid
+    /// client prefix or vendor.
+    InvalidTagKey,
+    /// The tags section exceeds 8191 bytes.
+    TagsTooLong,
+    InvalidPrefixName,
+    InvalidPrefixUser,
+    InvalidPrefixHost,
+    /// The command isn't a sequence of letters or exactly three digits.
+    InvalidCommand,
+    /// Two consecutive spaces or a space at the end of the message.
+    EmptyParam,
+    /// NUL, CR or LF inside the message.
+    InvalidCharacter,
+    /// The message has more than 15 params.
+    TooManyParams,
+    /// The message excluding tags exceeds 510 bytes.
+    LineTooLong,
 }
 
-impl std::fmt::Display for ParserError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for ParserErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ParserError::NoTagKeyEnd => write!(f, "Tag Key has no ending '='"),
-            ParserError::NoTagValueEnd => write!(f, "Tag Value has no ending ';' or ' '"),
-            ParserError::NoCommand => write!(f, "Missing command in message"),
-            ParserError::PrefixWithoutName => write!(f, "Prefix has to have name included"),
+            ParserErrorKind::NoTagKeyEnd => write!(f, "Tag Key has no ending '='"),
+            ParserErrorKind::NoTagValueEnd => write!(f, "Tag Value has no ending ';' or ' '"),
+            ParserErrorKind::NoCommand => write!(f, "Missing command in message"),
+            ParserErrorKind::PrefixWithoutName => write!(f, "Prefix has to have name included"),
+            ParserErrorKind::EmptyTagKey => write!(f, "Tag has an empty key"),
+            ParserErrorKind::InvalidTagKey => write!(f, "Tag Key is invalid"),
+            ParserErrorKind::TagsTooLong => write!(f, "Tags exceed 8191 bytes"),
+            ParserErrorKind::InvalidPrefixName => write!(f, "Prefix name is invalid"),
+            ParserErrorKind::InvalidPrefixUser => write!(f, "Prefix user is invalid"),
+            ParserErrorKind::InvalidPrefixHost => write!(f, "Prefix host is invalid"),
+            ParserErrorKind::InvalidCommand => {
+                write!(f, "Command has to be letters or a three digit numeric")
+            }
+            ParserErrorKind::EmptyParam => write!(f, "Empty parameter"),
+            ParserErrorKind::InvalidCharacter => write!(f, "Invalid character (NUL, CR or LF)"),
+            ParserErrorKind::TooManyParams => write!(f, "More than 15 parameters"),
+            ParserErrorKind::LineTooLong => write!(f, "Message exceeds 510 bytes"),
+        }
+    }
+}
+
+/// Error returned when parsing or validating a message.
+///
+/// Contains the byte offset and length of the offending region in the message and the
+/// component it concerns.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ParserError {
+    pub kind: ParserErrorKind,
+    pub component: Component,
+    /// Byte offset of the offending region in the message.
+    pub offset: usize,
+    /// Length of the offending region in bytes. May be 0 if something is missing.
+    pub length: usize,
+}
+
+impl ParserError {
+    pub fn new(kind: ParserErrorKind, component: Component, offset: usize, length: usize) -> Self {
+        ParserError {
+            kind,
+            component,
+            offset,
+            length,
         }
     }
+
+    /// Returns the byte range of the offending region.
+    pub fn span(&self) -> Range<usize> {
+        self.offset..self.offset + self.length
+    }
+
+    /// Returns a displayable diagnostic containing the error, the message it occurred in and
+    /// a caret line marking the offending region.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::Message;
+    ///
+    /// let line = "@key=value :name!user";
+    /// let why = Message::from(line).prefix().unwrap_err();
+    /// assert_eq!(
+    ///     "Missing command in message in command at byte 21\n@key=value :name!user\n                     ^",
+    ///     why.render(line).to_string()
+    /// );
+    /// ```
+    pub fn render<'a>(&'a self, line: &'a str) -> Rendered<'a> {
+        Rendered { error: self, line }
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in {} at byte {}",
+            self.kind, self.component, self.offset
+        )
+    }
 }
 
 impl Error for ParserError {}
+
+/// Diagnostic returned by [ParserError::render].
+#[derive(Debug, Clone, Copy)]
+pub struct Rendered<'a> {
+    error: &'a ParserError,
+    line: &'a str,
+}
+
+impl<'a> fmt::Display for Rendered<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = self.line.trim_end_matches(&['\r', '\n'][..]);
+        // Count chars instead of bytes to align the caret with multi-byte characters
+        let chars_until = |end: usize| {
+            line.char_indices()
+                .take_while(|(index, _)| *index < end)
+                .count()
+        };
+        let span = self.error.span();
+        let column = chars_until(span.start);
+        let width = (chars_until(span.end) - column).max(1);
+        writeln!(f, "{}", self.error)?;
+        // Control characters would break the layout of the line
+        let line: String = line
+            .chars()
+            .map(|ch| if ch.is_control() { ' ' } else { ch })
+            .collect();
+        writeln!(f, "{}", line)?;
+        write!(f, "{}{}", " ".repeat(column), "^".repeat(width))
+    }
+}
+
+/// Error returned when converting a [crate::parsed::Parsed] message into a typed message
+/// like [crate::typed::Privmsg].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ConversionError {
+    /// The message has no command or it hasn't been parsed.
+    NoCommand,
+    /// The command of the message doesn't match the typed message.
+    WrongCommand {
+        expected: &'static str,
+        found: String,
+    },
+    /// A required parameter is missing.
+    MissingParam {
+        command: &'static str,
+        index: usize,
+        name: &'static str,
+    },
+}
+
+impl std::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConversionError::NoCommand => write!(f, "Missing command in message"),
+            ConversionError::WrongCommand { expected, found } => {
+                write!(f, "Expected command '{}' but found '{}'", expected, found)
+            }
+            ConversionError::MissingParam {
+                command,
+                index,
+                name,
+            } => write!(
+                f,
+                "{} is missing parameter '{}' at index {}",
+                command, name, index
+            ),
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
+/// Error returned by [crate::codec::IrcCodec] while framing lines.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub enum CodecError {
+    /// A line exceeds the configured maximum line length.
+    LineTooLong { length: usize, max: usize },
+    /// A line isn't valid UTF-8.
+    InvalidUtf8(std::str::Utf8Error),
+    /// A line to encode contains a `\r`, `\n` or `\0` which would end or corrupt it.
+    InvalidCharacter(char),
+    /// Reading from or writing to the underlying transport failed.
+    Io(std::io::Error),
+}
+
+#[cfg(feature = "tokio")]
+impl std::fmt::Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodecError::LineTooLong { length, max } => write!(
+                f,
+                "Line of {} bytes exceeds the maximum line length of {} bytes",
+                length, max
+            ),
+            CodecError::InvalidUtf8(why) => write!(f, "Line is not valid UTF-8: {}", why),
+            CodecError::InvalidCharacter(character) => {
+                write!(f, "Line contains invalid character {:?}", character)
+            }
+            CodecError::Io(why) => write!(f, "IO error: {}", why),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Error for CodecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CodecError::LineTooLong { .. } | CodecError::InvalidCharacter(_) => None,
+            CodecError::InvalidUtf8(why) => Some(why),
+            CodecError::Io(why) => Some(why),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<std::io::Error> for CodecError {
+    fn from(why: std::io::Error) -> Self {
+        CodecError::Io(why)
+    }
+}
+
+/// Error returned by the fallible methods of [crate::builder::Builder] for input which
+/// would result in an invalid message.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BuildError {
+    EmptyCommand,
+    EmptyTagKey,
+    EmptyPrefixName,
+    EmptyPrefixUser,
+    EmptyPrefixHost,
+    PrefixUserWithoutHost,
+    EmptyParam,
+    /// A non-trailing param starts with ':' and would be read as trailing parameter.
+    ParamStartsWithColon,
+    /// The message has more than 15 params including the trailing parameter.
+    TooManyParams(usize),
+    /// A part of the message contains a character not allowed in it.
+    InvalidCharacter {
+        part: &'static str,
+        character: char,
+    },
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::EmptyCommand => write!(f, "tried to set empty command"),
+            BuildError::EmptyTagKey => write!(f, "tried to set tag with empty key"),
+            BuildError::EmptyPrefixName => write!(f, "tried to set empty prefix name"),
+            BuildError::EmptyPrefixUser => write!(f, "tried to set empty prefix user"),
+            BuildError::EmptyPrefixHost => write!(f, "tried to set empty prefix host"),
+            BuildError::PrefixUserWithoutHost => {
+                write!(f, "tried to set prefix user without host")
+            }
+            BuildError::EmptyParam => write!(f, "tried to set empty param"),
+            BuildError::ParamStartsWithColon => {
+                write!(f, "tried to set param starting with ':'")
+            }
+            BuildError::TooManyParams(count) => {
+                write!(f, "message has {} params but at most 15 are allowed", count)
+            }
+            BuildError::InvalidCharacter { part, character } => {
+                write!(f, "{} contains invalid character {:?}", part, character)
+            }
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// Error returned when parsing a name like a [crate::charset::Charset] or
+/// [crate::casemap::CaseMapping] which isn't known.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnknownNameError {
+    /// What the name was supposed to name, e.g. `charset`.
+    pub kind: &'static str,
+    pub name: String,
+}
+
+impl std::fmt::Display for UnknownNameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown {} '{}'", self.kind, self.name)
+    }
+}
+
+impl Error for UnknownNameError {}
+
+/// Error returned when converting a [crate::ctcp::Ctcp] message into a [crate::dcc::Dcc] offer.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DccError {
+    /// The CTCP command isn't `DCC`.
+    NotDcc,
+    /// The DCC type isn't one of `SEND`, `CHAT`, `RESUME` or `ACCEPT`.
+    UnknownType(String),
+    MissingArgument(&'static str),
+    /// The address is neither an IPv4 address as integer nor an IP address.
+    InvalidAddress(String),
+    InvalidNumber {
+        name: &'static str,
+        value: String,
+    },
+}
+
+impl std::fmt::Display for DccError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DccError::NotDcc => write!(f, "CTCP message isn't a DCC offer"),
+            DccError::UnknownType(kind) => write!(f, "Unknown DCC type '{}'", kind),
+            DccError::MissingArgument(name) => write!(f, "DCC is missing argument '{}'", name),
+            DccError::InvalidAddress(address) => write!(f, "Invalid DCC address '{}'", address),
+            DccError::InvalidNumber { name, value } => {
+                write!(
+                    f,
+                    "DCC argument '{}' is not a valid number: '{}'",
+                    name, value
+                )
+            }
+        }
+    }
+}
+
+impl Error for DccError {}
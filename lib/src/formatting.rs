@@ -0,0 +1,387 @@
+//! Parsing of mIRC formatting codes in message text.
+//!
+//! Supported are the control codes described in the
+//! [modern IRC formatting documentation](https://modern.ircdocs.horse/formatting.html):
+//!
+//! | Code   | Meaning                                |
+//! |--------|----------------------------------------|
+//! | `\x02` | Bold                                   |
+//! | `\x03` | Colour as `\x03fg,bg` with 0 to 99     |
+//! | `\x04` | Hex colour as `\x04RRGGBB,RRGGBB`      |
+//! | `\x0F` | Reset all formatting                   |
+//! | `\x11` | Monospace                              |
+//! | `\x16` | Reverse foreground and background      |
+//! | `\x1D` | Italic                                 |
+//! | `\x1E` | Strikethrough                          |
+//! | `\x1F` | Underline                              |
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::formatting::{parse, strip_formatting, Color, Span, Style};
+//!
+//! let text = "\x02bold\x02 \x034,12red on blue\x03 plain";
+//! let spans = parse(text).collect::<Vec<_>>();
+//! assert_eq!(
+//!     vec![
+//!         Span { text: "bold", style: Style { bold: true, ..Style::default() } },
+//!         Span { text: " ", style: Style::default() },
+//!         Span {
+//!             text: "red on blue",
+//!             style: Style {
+//!                 foreground: Some(Color::Palette(4)),
+//!                 background: Some(Color::Palette(12)),
+//!                 ..Style::default()
+//!             }
+//!         },
+//!         Span { text: " plain", style: Style::default() },
+//!     ],
+//!     spans
+//! );
+//! assert_eq!("bold red on blue plain", strip_formatting(text));
+//! ```
+
+use std::borrow::Cow;
+
+pub mod ansi;
+pub mod html;
+pub mod markdown;
+
+pub const BOLD: char = '\x02';
+pub const COLOR: char = '\x03';
+pub const HEX_COLOR: char = '\x04';
+pub const RESET: char = '\x0F';
+pub const MONOSPACE: char = '\x11';
+pub const REVERSE: char = '\x16';
+pub const ITALIC: char = '\x1D';
+pub const STRIKETHROUGH: char = '\x1E';
+pub const UNDERLINE: char = '\x1F';
+
+const CODES: [char; 9] = [
+    BOLD,
+    COLOR,
+    HEX_COLOR,
+    RESET,
+    MONOSPACE,
+    REVERSE,
+    ITALIC,
+    STRIKETHROUGH,
+    UNDERLINE,
+];
+
+/// Colour code meaning the default colour of the client.
+const DEFAULT_COLOR: u8 = 99;
+
+/// RGB values of the 99 colours of the palette as defined by the
+/// [modern IRC formatting documentation](https://modern.ircdocs.horse/formatting.html#colors-16-98).
+const PALETTE: [u32; 99] = [
+    // 0 to 15: classic mIRC colours
+    0xffffff, 0x000000, 0x00007f, 0x009300, 0xff0000, 0x7f0000, 0x9c009c, 0xfc7f00, 0xffff00,
+    0x00fc00, 0x009393, 0x00ffff, 0x0000fc, 0xff00ff, 0x7f7f7f, 0xd2d2d2,
+    // 16 to 98: extended colours
+    0x470000, 0x472100, 0x474700, 0x324700, 0x004700, 0x00472c, 0x004747, 0x002747, 0x000047,
+    0x2e0047, 0x470047, 0x47002a, 0x740000, 0x743a00, 0x747400, 0x517400, 0x007400, 0x007449,
+    0x007474, 0x004074, 0x000074, 0x4b0074, 0x740074, 0x740045, 0xb50000, 0xb56300, 0xb5b500,
+    0x7db500, 0x00b500, 0x00b571, 0x00b5b5, 0x0063b5, 0x0000b5, 0x7500b5, 0xb500b5, 0xb5006b,
+    0xff0000, 0xff8c00, 0xffff00, 0xb2ff00, 0x00ff00, 0x00ffa0, 0x00ffff, 0x008cff, 0x0000ff,
+    0xa500ff, 0xff00ff, 0xff0098, 0xff5959, 0xffb459, 0xffff71, 0xcfff60, 0x6fff6f, 0x65ffc9,
+    0x6dffff, 0x59b4ff, 0x5959ff, 0xc459ff, 0xff66ff, 0xff59bc, 0xff9c9c, 0xffd39c, 0xffff9c,
+    0xe2ff9c, 0x9cff9c, 0x9cffdb, 0x9cffff, 0x9cd3ff, 0x9c9cff, 0xdc9cff, 0xff9cff, 0xff94d3,
+    0x000000, 0x131313, 0x282828, 0x363636, 0x4d4d4d, 0x656565, 0x818181, 0x9f9f9f, 0xbcbcbc,
+    0xe2e2e2, 0xffffff,
+];
+
+/// A parsed colour argument and the number of consumed bytes.
+type ColorArgument = Option<(Option<Color>, usize)>;
+
+/// A foreground or background colour.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Color {
+    /// A colour of the palette from 0 to 98. 0 to 15 are the classic mIRC colours.
+    Palette(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Returns the RGB value of the colour or [None] for palette colours above 98.
+    pub fn rgb(self) -> Option<(u8, u8, u8)> {
+        match self {
+            Color::Palette(index) => PALETTE.get(usize::from(index)).map(|rgb| {
+                let [_, red, green, blue] = rgb.to_be_bytes();
+                (red, green, blue)
+            }),
+            Color::Rgb(red, green, blue) => Some((red, green, blue)),
+        }
+    }
+
+    /// Returns the colour as CSS hex colour like `#ff0000`.
+    pub fn css(self) -> Option<String> {
+        self.rgb()
+            .map(|(red, green, blue)| format!("#{:02x}{:02x}{:02x}", red, green, blue))
+    }
+}
+
+/// The formatting applied to a [Span].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub monospace: bool,
+    /// Foreground and background colour are swapped.
+    pub reverse: bool,
+    /// [None] for the default colour.
+    pub foreground: Option<Color>,
+    /// [None] for the default colour.
+    pub background: Option<Color>,
+}
+
+impl Style {
+    /// Returns true if no formatting is applied.
+    pub fn is_plain(&self) -> bool {
+        *self == Style::default()
+    }
+}
+
+/// A part of a text with the same formatting.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Span<'a> {
+    pub text: &'a str,
+    pub style: Style,
+}
+
+/// Parses text into [Span]s with the same formatting. Empty spans are skipped.
+pub fn parse(text: &str) -> Spans<'_> {
+    Spans {
+        rest: text,
+        style: Style::default(),
+    }
+}
+
+/// Removes all formatting codes including colour arguments from the text.
+///
+/// Returns the text borrowed if it doesn't contain formatting codes.
+pub fn strip_formatting(text: &str) -> Cow<'_, str> {
+    if !text.contains(&CODES[..]) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(parse(text).map(|span| span.text).collect())
+}
+
+/// Iterator over the [Span]s of a text returned by [parse].
+#[derive(Debug, Clone)]
+pub struct Spans<'a> {
+    rest: &'a str,
+    style: Style,
+}
+
+impl<'a> Spans<'a> {
+    /// Parses up to two digits of a colour code and returns the colour and the number of
+    /// consumed bytes.
+    fn palette_color(text: &str) -> ColorArgument {
+        let len = text
+            .bytes()
+            .take(2)
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        let code = text[..len].parse::<u8>().ok()?;
+        let color = if code == DEFAULT_COLOR {
+            None
+        } else {
+            Some(Color::Palette(code))
+        };
+        Some((color, len))
+    }
+
+    /// Parses six hex digits of a hex colour code and returns the colour and the number of
+    /// consumed bytes.
+    fn hex_color(text: &str) -> ColorArgument {
+        let hex = text.get(..6)?;
+        if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+        Some((Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?)), 6))
+    }
+
+    /// Parses the arguments of a colour code with the given parser and applies them to the
+    /// style. Resets both colours if no argument is present.
+    fn color(&mut self, parse: fn(&str) -> ColorArgument) {
+        match parse(self.rest) {
+            Some((foreground, len)) => {
+                self.style.foreground = foreground;
+                self.rest = &self.rest[len..];
+                if let Some(rest) = self.rest.strip_prefix(',') {
+                    if let Some((background, len)) = parse(rest) {
+                        self.style.background = background;
+                        self.rest = &rest[len..];
+                    }
+                }
+            }
+            None => {
+                self.style.foreground = None;
+                self.style.background = None;
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Spans<'a> {
+    type Item = Span<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut chars = self.rest.chars();
+            let code = chars.next()?;
+            if CODES.contains(&code) {
+                self.rest = chars.as_str();
+                match code {
+                    BOLD => self.style.bold = !self.style.bold,
+                    ITALIC => self.style.italic = !self.style.italic,
+                    UNDERLINE => self.style.underline = !self.style.underline,
+                    STRIKETHROUGH => self.style.strikethrough = !self.style.strikethrough,
+                    MONOSPACE => self.style.monospace = !self.style.monospace,
+                    REVERSE => self.style.reverse = !self.style.reverse,
+                    COLOR => self.color(Spans::palette_color),
+                    HEX_COLOR => self.color(Spans::hex_color),
+                    _ => self.style = Style::default(),
+                }
+                continue;
+            }
+
+            let end = self.rest.find(&CODES[..]).unwrap_or(self.rest.len());
+            let (text, rest) = self.rest.split_at(end);
+            self.rest = rest;
+            return Some(Span {
+                text,
+                style: self.style,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formatting::{parse, strip_formatting, Color, Span, Style};
+    use std::borrow::Cow;
+
+    fn styles(text: &str) -> Vec<(&str, Style)> {
+        parse(text).map(|span| (span.text, span.style)).collect()
+    }
+
+    #[test]
+    fn test_toggles() {
+        let bold = Style {
+            bold: true,
+            ..Style::default()
+        };
+        let bold_italic = Style {
+            italic: true,
+            ..bold
+        };
+        assert_eq!(
+            vec![
+                ("a", bold),
+                ("b", bold_italic),
+                ("c", bold),
+                ("d", Style::default())
+            ],
+            styles("\x02a\x1db\x1dc\x02d")
+        );
+        let all = Style {
+            underline: true,
+            strikethrough: true,
+            monospace: true,
+            reverse: true,
+            ..bold_italic
+        };
+        assert_eq!(
+            vec![("a", all), ("b", Style::default())],
+            styles("\x02\x1d\x1f\x1e\x11\x16a\x0fb")
+        );
+    }
+
+    #[test]
+    fn test_colors() {
+        let color = |foreground: Option<u8>, background: Option<u8>| Style {
+            foreground: foreground.map(Color::Palette),
+            background: background.map(Color::Palette),
+            ..Style::default()
+        };
+        assert_eq!(vec![("a", color(Some(4), None))], styles("\x034a"));
+        assert_eq!(vec![("a", color(Some(4), Some(12)))], styles("\x0304,12a"));
+        // Only two digits are part of the code
+        assert_eq!(vec![("123", color(Some(12), None))], styles("\x0312123"));
+        assert_eq!(vec![("5", color(Some(88), Some(2)))], styles("\x0388,025"));
+        // A comma without digits is text
+        assert_eq!(vec![(",a", color(Some(4), None))], styles("\x034,a"));
+        // Background is kept if only the foreground changes
+        assert_eq!(
+            vec![
+                ("a", color(Some(4), Some(1))),
+                ("b", color(Some(5), Some(1)))
+            ],
+            styles("\x034,1a\x035b")
+        );
+        // 99 is the default colour
+        assert_eq!(vec![("a", color(None, Some(1)))], styles("\x034,1\x0399a"));
+        // Colour code without digits resets both colours
+        assert_eq!(
+            vec![("a", color(Some(4), Some(1))), ("b", Style::default())],
+            styles("\x034,1a\x03b")
+        );
+    }
+
+    #[test]
+    fn test_hex_colors() {
+        assert_eq!(
+            vec![(
+                "a",
+                Style {
+                    foreground: Some(Color::Rgb(0xff, 0x00, 0x80)),
+                    background: Some(Color::Rgb(0x12, 0x34, 0x56)),
+                    ..Style::default()
+                }
+            )],
+            styles("\x04FF0080,123456a")
+        );
+        assert_eq!(vec![("12345g", Style::default())], styles("\x0412345g"));
+    }
+
+    #[test]
+    fn test_spans() {
+        assert_eq!(None, parse("").next());
+        assert_eq!(None, parse("\x02\x02").next());
+        assert_eq!(
+            Some(Span {
+                text: "plain text",
+                style: Style::default()
+            }),
+            parse("plain text").next()
+        );
+        assert!(parse("plain").next().unwrap().style.is_plain());
+    }
+
+    #[test]
+    fn test_palette() {
+        assert_eq!(Some((0xff, 0xff, 0xff)), Color::Palette(0).rgb());
+        assert_eq!(Some((0xfc, 0x7f, 0x00)), Color::Palette(7).rgb());
+        assert_eq!(Some((0x47, 0x00, 0x00)), Color::Palette(16).rgb());
+        assert_eq!(Some((0xff, 0xff, 0xff)), Color::Palette(98).rgb());
+        assert_eq!(None, Color::Palette(99).rgb());
+        assert_eq!(
+            Some("#123456".to_string()),
+            Color::Rgb(0x12, 0x34, 0x56).css()
+        );
+    }
+
+    #[test]
+    fn test_strip_formatting() {
+        assert!(matches!(strip_formatting("plain"), Cow::Borrowed("plain")));
+        assert_eq!(
+            "bold italic 5 colors",
+            strip_formatting("\x02bold\x02 \x1ditalic\x0f \x0312,015 \x04ff0000colors\x03")
+        );
+    }
+}
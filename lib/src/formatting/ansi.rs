@@ -0,0 +1,173 @@
+//! Rendering of formatted text as ANSI SGR escape sequences for terminals.
+//!
+//! Palette colours are either mapped to the 256 colour palette of terminals or rendered as
+//! 24-bit colours with the same RGB values as [html](crate::formatting::html). Control
+//! characters in the text are replaced by `U+FFFD` so messages can't inject escape
+//! sequences. Monospace has no effect as terminals are monospaced.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::formatting::ansi::{render, ColorMode};
+//! use irc_rust::formatting::parse;
+//!
+//! let text = "\x02bold\x02 and \x034red";
+//! assert_eq!(
+//!     "\x1b[0;1mbold\x1b[0m and \x1b[0;38;5;9mred\x1b[0m",
+//!     render(parse(text), ColorMode::Indexed)
+//! );
+//! assert_eq!(
+//!     "\x1b[0;1mbold\x1b[0m and \x1b[0;38;2;255;0;0mred\x1b[0m",
+//!     render(parse(text), ColorMode::TrueColor)
+//! );
+//! ```
+
+use crate::formatting::{Color, Span, Style};
+use std::fmt::Write;
+
+/// Codes of the 256 colour palette of terminals for the 99 colours of the IRC palette as
+/// defined by the
+/// [modern IRC formatting documentation](https://modern.ircdocs.horse/formatting.html#colors-16-98).
+const ANSI_PALETTE: [u8; 99] = [
+    // 0 to 15
+    15, 0, 4, 2, 9, 1, 5, 202, 11, 10, 6, 14, 12, 13, 8, 7, // 16 to 98
+    52, 94, 100, 58, 22, 29, 23, 24, 17, 54, 53, 89, 88, 130, 142, 64, 28, 35, 30, 25, 18, 91, 90,
+    125, 124, 166, 184, 106, 34, 49, 37, 33, 19, 129, 127, 161, 196, 208, 226, 154, 46, 86, 51, 75,
+    21, 171, 201, 198, 203, 215, 227, 191, 83, 122, 87, 111, 63, 177, 207, 205, 217, 223, 229, 193,
+    157, 158, 159, 153, 147, 183, 219, 212, 16, 233, 235, 237, 239, 241, 244, 247, 250, 254, 231,
+];
+
+/// How colours are rendered.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ColorMode {
+    /// Colours of the 256 colour palette. Hex colours are approximated.
+    Indexed,
+    /// 24-bit colours.
+    TrueColor,
+}
+
+/// Returns the code of the 256 colour palette closest to the colour or [None] for palette
+/// colours above 98.
+pub fn indexed_color(color: Color) -> Option<u8> {
+    match color {
+        Color::Palette(index) => ANSI_PALETTE.get(usize::from(index)).copied(),
+        Color::Rgb(red, green, blue) => {
+            // Map to the 6x6x6 colour cube
+            let level = |value: u8| ((u16::from(value) * 5 + 127) / 255) as u8;
+            Some(16 + 36 * level(red) + 6 * level(green) + level(blue))
+        }
+    }
+}
+
+/// Returns the parameters of the SGR sequence for a style.
+fn sgr(style: &Style, mode: ColorMode) -> String {
+    let mut params = String::from("0");
+    for (enabled, code) in [
+        (style.bold, 1),
+        (style.italic, 3),
+        (style.underline, 4),
+        (style.reverse, 7),
+        (style.strikethrough, 9),
+    ]
+    .iter()
+    {
+        if *enabled {
+            let _ = write!(params, ";{}", code);
+        }
+    }
+    for (color, base) in [(style.foreground, 38), (style.background, 48)].iter() {
+        let color = match color {
+            Some(color) => color,
+            None => continue,
+        };
+        match mode {
+            ColorMode::Indexed => {
+                if let Some(code) = indexed_color(*color) {
+                    let _ = write!(params, ";{};5;{}", base, code);
+                }
+            }
+            ColorMode::TrueColor => {
+                if let Some((red, green, blue)) = color.rgb() {
+                    let _ = write!(params, ";{};2;{};{};{}", base, red, green, blue);
+                }
+            }
+        }
+    }
+    params
+}
+
+/// Renders spans with ANSI SGR escape sequences. The output ends with the default style.
+pub fn render<'a, I: IntoIterator<Item = Span<'a>>>(spans: I, mode: ColorMode) -> String {
+    let mut output = String::new();
+    let mut current = Style::default();
+    for span in spans {
+        if span.style != current {
+            if span.style.is_plain() {
+                output.push_str("\x1b[0m");
+            } else {
+                let _ = write!(output, "\x1b[{}m", sgr(&span.style, mode));
+            }
+            current = span.style;
+        }
+        output.extend(span.text.chars().map(|ch| {
+            if ch.is_control() {
+                char::REPLACEMENT_CHARACTER
+            } else {
+                ch
+            }
+        }));
+    }
+    if !current.is_plain() {
+        output.push_str("\x1b[0m");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formatting::ansi::{indexed_color, render, ColorMode};
+    use crate::formatting::{parse, Color};
+
+    #[test]
+    fn test_indexed_color() {
+        assert_eq!(Some(15), indexed_color(Color::Palette(0)));
+        assert_eq!(Some(202), indexed_color(Color::Palette(7)));
+        assert_eq!(Some(52), indexed_color(Color::Palette(16)));
+        assert_eq!(Some(231), indexed_color(Color::Palette(98)));
+        assert_eq!(None, indexed_color(Color::Palette(99)));
+        assert_eq!(Some(196), indexed_color(Color::Rgb(0xff, 0, 0)));
+        assert_eq!(Some(16), indexed_color(Color::Rgb(0, 0, 0)));
+    }
+
+    #[test]
+    fn test_attributes() {
+        assert_eq!(
+            "\x1b[0;1;3;4;7;9ma\x1b[0;1mb\x1b[0m",
+            render(
+                parse("\x02\x1d\x1f\x16\x1e\x11a\x1d\x1f\x16\x1e\x11b"),
+                ColorMode::Indexed
+            )
+        );
+        assert_eq!("plain", render(parse("plain"), ColorMode::Indexed));
+    }
+
+    #[test]
+    fn test_colors() {
+        assert_eq!(
+            "\x1b[0;38;5;52;48;5;16ma\x1b[0m",
+            render(parse("\x0316,88a"), ColorMode::Indexed)
+        );
+        assert_eq!(
+            "\x1b[0;38;2;71;0;0;48;2;18;52;86ma\x1b[0m",
+            render(parse("\x04000000,123456\x0316a"), ColorMode::TrueColor)
+        );
+    }
+
+    #[test]
+    fn test_control_characters() {
+        assert_eq!(
+            "\u{FFFD}[31mred?",
+            render(parse("\x1b[31mred?"), ColorMode::Indexed)
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Rendering of formatted text as HTML.
+//!
+//! Text is escaped and spans with formatting are wrapped in `<span>` elements using either
+//! CSS classes or inline styles. [stylesheet] returns the CSS for the classes including
+//! all colours of the palette.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::formatting::html::{render, Mode};
+//! use irc_rust::formatting::parse;
+//!
+//! let text = "<b> is \x02bold\x02 and \x034red";
+//! assert_eq!(
+//!     "&lt;b&gt; is <span class=\"irc-bold\">bold</span> and <span class=\"irc-fg-4\">red</span>",
+//!     render(parse(text), Mode::Classes)
+//! );
+//! assert_eq!(
+//!     "&lt;b&gt; is <span style=\"font-weight: bold\">bold</span> and <span style=\"color: #ff0000\">red</span>",
+//!     render(parse(text), Mode::InlineStyles)
+//! );
+//! ```
+
+use crate::formatting::{Color, Span, Style, PALETTE};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// How the formatting of spans is expressed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Mode {
+    /// CSS classes prefixed with `irc-` defined by [stylesheet]. Hex colours are always
+    /// rendered as inline styles.
+    Classes,
+    /// Inline `style` attributes.
+    InlineStyles,
+}
+
+/// Escapes the characters with special meaning in HTML.
+///
+/// Returns the text borrowed if nothing has to be escaped.
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(&['&', '<', '>', '"', '\''][..]) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Returns the CSS rules for the classes used by [Mode::Classes].
+pub fn stylesheet() -> String {
+    let mut css = String::from(
+        ".irc-bold { font-weight: bold; }\n\
+         .irc-italic { font-style: italic; }\n\
+         .irc-underline { text-decoration-line: underline; }\n\
+         .irc-strikethrough { text-decoration-line: line-through; }\n\
+         .irc-underline.irc-strikethrough { text-decoration-line: underline line-through; }\n\
+         .irc-monospace { font-family: monospace; }\n\
+         .irc-reverse { filter: invert(100%); }\n",
+    );
+    for index in 0..PALETTE.len() {
+        let color = Color::Palette(index as u8).css().unwrap_or_default();
+        let _ = writeln!(css, ".irc-fg-{} {{ color: {}; }}", index, color);
+        let _ = writeln!(css, ".irc-bg-{} {{ background-color: {}; }}", index, color);
+    }
+    css
+}
+
+/// Renders spans as HTML.
+pub fn render<'a, I: IntoIterator<Item = Span<'a>>>(spans: I, mode: Mode) -> String {
+    let mut html = String::new();
+    for span in spans {
+        let text = escape(span.text);
+        if span.style.is_plain() {
+            html.push_str(&text);
+            continue;
+        }
+        let (classes, styles) = attributes(&span.style, mode);
+        html.push_str("<span");
+        if !classes.is_empty() {
+            let _ = write!(html, " class=\"{}\"", classes.join(" "));
+        }
+        if !styles.is_empty() {
+            let _ = write!(html, " style=\"{}\"", styles.join("; "));
+        }
+        let _ = write!(html, ">{}</span>", text);
+    }
+    html
+}
+
+/// Returns the classes and inline styles of a style.
+fn attributes(style: &Style, mode: Mode) -> (Vec<String>, Vec<String>) {
+    let mut classes = Vec::new();
+    let mut styles = Vec::new();
+    let mut add = |names: &[&str], css: &str| match mode {
+        Mode::Classes => classes.extend(names.iter().map(|name| format!("irc-{}", name))),
+        Mode::InlineStyles => styles.push(css.to_string()),
+    };
+    if style.bold {
+        add(&["bold"], "font-weight: bold");
+    }
+    if style.italic {
+        add(&["italic"], "font-style: italic");
+    }
+    match (style.underline, style.strikethrough) {
+        (true, true) => add(
+            &["underline", "strikethrough"],
+            "text-decoration-line: underline line-through",
+        ),
+        (true, false) => add(&["underline"], "text-decoration-line: underline"),
+        (false, true) => add(&["strikethrough"], "text-decoration-line: line-through"),
+        (false, false) => {}
+    }
+    if style.monospace {
+        add(&["monospace"], "font-family: monospace");
+    }
+
+    let (foreground, background) = if style.reverse {
+        (style.background, style.foreground)
+    } else {
+        (style.foreground, style.background)
+    };
+    if style.reverse && foreground.is_none() && background.is_none() {
+        // Without colours the default colours have to be inverted
+        add(&["reverse"], "filter: invert(100%)");
+    }
+    for (color, class, property) in [
+        (foreground, "fg", "color"),
+        (background, "bg", "background-color"),
+    ]
+    .iter()
+    {
+        match (color, mode) {
+            (Some(Color::Palette(index)), Mode::Classes) if usize::from(*index) < PALETTE.len() => {
+                classes.push(format!("irc-{}-{}", class, index))
+            }
+            (Some(color), _) => {
+                if let Some(css) = color.css() {
+                    styles.push(format!("{}: {}", property, css));
+                }
+            }
+            (None, _) => {}
+        }
+    }
+    (classes, styles)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formatting::html::{escape, render, stylesheet, Mode};
+    use crate::formatting::parse;
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_escape() {
+        assert!(matches!(escape("plain"), Cow::Borrowed("plain")));
+        assert_eq!(
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;",
+            escape("<a href=\"x\">&'")
+        );
+    }
+
+    #[test]
+    fn test_classes() {
+        assert_eq!(
+            "<span class=\"irc-bold irc-italic irc-underline irc-strikethrough irc-monospace irc-fg-52 irc-bg-1\">a</span>",
+            render(parse("\x02\x1d\x1f\x1e\x11\x0352,1a"), Mode::Classes)
+        );
+        assert_eq!(
+            "<span class=\"irc-bg-4\" style=\"color: #ff00aa\">a</span>",
+            render(parse("\x0399,4\x04FF00AAa"), Mode::Classes)
+        );
+    }
+
+    #[test]
+    fn test_inline_styles() {
+        assert_eq!(
+            "<span style=\"font-style: italic; text-decoration-line: underline line-through; color: #000000; background-color: #00fc00\">a</span>",
+            render(parse("\x1d\x1f\x1e\x031,9a"), Mode::InlineStyles)
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        assert_eq!(
+            "<span class=\"irc-fg-9 irc-bg-1\">a</span>",
+            render(parse("\x031,9\x16a"), Mode::Classes)
+        );
+        assert_eq!(
+            "<span style=\"filter: invert(100%)\">a</span>",
+            render(parse("\x16a"), Mode::InlineStyles)
+        );
+    }
+
+    #[test]
+    fn test_stylesheet() {
+        let css = stylesheet();
+        assert!(css.contains(".irc-fg-0 { color: #ffffff; }"));
+        assert!(css.contains(".irc-bg-98 { background-color: #ffffff; }"));
+        assert!(!css.contains("irc-fg-99"));
+    }
+}
@@ -0,0 +1,410 @@
+//! Conversion between a Markdown subset and mIRC formatting codes.
+//!
+//! | Markdown                   | IRC            |
+//! |----------------------------|----------------|
+//! | `**bold**` or `__bold__`   | `\x02`         |
+//! | `*italic*` or `_italic_`   | `\x1D`         |
+//! | `~~strikethrough~~`        | `\x1E`         |
+//! | `` `code` ``               | `\x11`         |
+//! | Code blocks                | `\x11` per line |
+//!
+//! Colours, underline and reverse have no Markdown equivalent and are dropped by
+//! [from_irc]. Whitespace at the start or end of formatted text can't be formatted in
+//! Markdown and is moved outside of the delimiters. Apart from that, converting IRC text to
+//! Markdown and back keeps the formatting and converting the Markdown produced by
+//! [from_irc] to IRC and back yields the same Markdown.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::formatting::markdown::{from_irc, to_irc};
+//! use irc_rust::Message;
+//! # fn main() -> Result<(), irc_rust::errors::ParserError> {
+//! let message = Message::from("PRIVMSG #channel :\x02Hello\x02 \x1dworld\x1d with \x11code*\x11!");
+//! let markdown = from_irc(message.parse()?.trailing().unwrap());
+//! assert_eq!("**Hello** *world* with `code*`!", markdown);
+//!
+//! let lines = to_irc("Reply with ~~struck~~ __text__\n```\nfn main() {}\n```");
+//! let message = Message::builder("PRIVMSG")
+//!     .param("#channel")
+//!     .trailing(&lines[0])
+//!     .build();
+//! assert_eq!("PRIVMSG #channel :Reply with \x1estruck\x1e \x02text\x02", message.to_string());
+//! assert_eq!("\x11fn main() {}\x11", lines[1]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::formatting::{parse, BOLD, ITALIC, MONOSPACE, STRIKETHROUGH};
+
+/// Delimiters in the order they are tested and the code and slot of the formatting.
+const DELIMITERS: [(&str, char, usize); 5] = [
+    ("**", BOLD, 0),
+    ("__", BOLD, 0),
+    ("~~", STRIKETHROUGH, 2),
+    ("*", ITALIC, 1),
+    ("_", ITALIC, 1),
+];
+
+/// Characters escaped with a backslash when converting to Markdown.
+const ESCAPED: [char; 5] = ['\\', '*', '_', '~', '`'];
+
+/// Converts Markdown into IRC formatted lines which can be used as trailing parameters.
+///
+/// Each line of the Markdown results in one line. Lines of code blocks are sent as
+/// monospace and the fences are dropped.
+pub fn to_irc(markdown: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        } else if in_code_block {
+            if line.is_empty() {
+                lines.push(String::new());
+            } else {
+                lines.push(format!("{}{}{}", MONOSPACE, line, MONOSPACE));
+            }
+        } else {
+            lines.push(line_to_irc(line));
+        }
+    }
+    lines
+}
+
+/// Returns true if the chars at **index** start with **delimiter**.
+fn starts_with(chars: &[char], index: usize, delimiter: &str) -> bool {
+    delimiter
+        .chars()
+        .enumerate()
+        .all(|(offset, ch)| chars.get(index + offset) == Some(&ch))
+}
+
+fn is_whitespace(ch: Option<&char>) -> bool {
+    ch.map(|ch| ch.is_whitespace()).unwrap_or(true)
+}
+
+fn is_alphanumeric(ch: Option<&char>) -> bool {
+    ch.map(|ch| ch.is_alphanumeric()).unwrap_or(false)
+}
+
+/// Returns true if **delimiter** at **index** can close formatting.
+fn can_close(chars: &[char], index: usize, delimiter: &str) -> bool {
+    let before = index.checked_sub(1).and_then(|before| chars.get(before));
+    let after = chars.get(index + delimiter.len());
+    // Underscores don't format inside of words
+    !is_whitespace(before) && (!delimiter.starts_with('_') || !is_alphanumeric(after))
+}
+
+/// Returns true if **delimiter** at **index** can open formatting which is closed later.
+fn can_open(chars: &[char], index: usize, delimiter: &str) -> bool {
+    let before = index.checked_sub(1).and_then(|before| chars.get(before));
+    let start = index + delimiter.len();
+    if is_whitespace(chars.get(start)) || (delimiter.starts_with('_') && is_alphanumeric(before)) {
+        return false;
+    }
+    // Escapes are paired from the first char, but formatting can't be empty
+    let mut index = start;
+    while index < chars.len() {
+        if chars[index] == '\\' {
+            index += 2;
+        } else if index > start
+            && starts_with(chars, index, delimiter)
+            && can_close(chars, index, delimiter)
+        {
+            return true;
+        } else {
+            index += 1;
+        }
+    }
+    false
+}
+
+/// Returns the position of the closing backtick run of a code span.
+fn code_span_end(chars: &[char], start: usize, run: usize) -> Option<usize> {
+    let mut index = start;
+    while index < chars.len() {
+        if chars[index] != '`' {
+            index += 1;
+            continue;
+        }
+        let end = index + chars[index..].iter().take_while(|ch| **ch == '`').count();
+        if end - index == run {
+            return Some(index);
+        }
+        index = end;
+    }
+    None
+}
+
+/// Converts a line of Markdown outside of code blocks.
+fn line_to_irc(line: &str) -> String {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut irc = String::with_capacity(line.len());
+    // Delimiter which opened bold, italic and strikethrough
+    let mut open: [Option<&str>; 3] = [None; 3];
+    let mut index = 0;
+    'chars: while index < chars.len() {
+        let ch = chars[index];
+        if ch == '\\' && chars.get(index + 1).is_some_and(char::is_ascii_punctuation) {
+            irc.push(chars[index + 1]);
+            index += 2;
+            continue;
+        }
+        if ch == '`' {
+            let run = chars[index..].iter().take_while(|ch| **ch == '`').count();
+            if let Some(end) = code_span_end(&chars, index + run, run) {
+                let mut code = &chars[index + run..end];
+                // One space is stripped on both sides to allow code starting with backticks
+                if code.len() > 2
+                    && code[0] == ' '
+                    && code[code.len() - 1] == ' '
+                    && code.iter().any(|ch| *ch != ' ')
+                {
+                    code = &code[1..code.len() - 1];
+                }
+                irc.push(MONOSPACE);
+                irc.extend(code);
+                irc.push(MONOSPACE);
+                index = end + run;
+            } else {
+                irc.extend(&chars[index..index + run]);
+                index += run;
+            }
+            continue;
+        }
+        for (delimiter, code, slot) in DELIMITERS.iter() {
+            if !starts_with(&chars, index, delimiter) {
+                continue;
+            }
+            let toggles = match open[*slot] {
+                Some(opened) => opened == *delimiter && can_close(&chars, index, delimiter),
+                None => can_open(&chars, index, delimiter),
+            };
+            if toggles {
+                open[*slot] = if open[*slot].is_some() {
+                    None
+                } else {
+                    Some(delimiter)
+                };
+                irc.push(*code);
+                index += delimiter.len();
+                continue 'chars;
+            }
+        }
+        irc.push(ch);
+        index += 1;
+    }
+    irc
+}
+
+/// Formatting expressible in Markdown.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Marker {
+    Bold = 0,
+    Italic = 1,
+    Strikethrough = 2,
+}
+
+const MARKERS: [Marker; 3] = [Marker::Bold, Marker::Italic, Marker::Strikethrough];
+
+impl Marker {
+    fn delimiter(self) -> &'static str {
+        match self {
+            Marker::Bold => "**",
+            Marker::Italic => "*",
+            Marker::Strikethrough => "~~",
+        }
+    }
+}
+
+/// Writes text as code span with a fence longer than any backtick run in the text.
+fn push_code_span(markdown: &mut String, code: &str) {
+    let mut longest = 0;
+    let mut current = 0;
+    for ch in code.chars() {
+        current = if ch == '`' { current + 1 } else { 0 };
+        longest = longest.max(current);
+    }
+    let fence = "`".repeat(longest + 1);
+    let padding = if code.starts_with('`') || code.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    markdown.push_str(&fence);
+    markdown.push_str(padding);
+    markdown.push_str(code);
+    markdown.push_str(padding);
+    markdown.push_str(&fence);
+}
+
+/// Converts IRC formatted text like a trailing parameter into Markdown.
+pub fn from_irc(text: &str) -> String {
+    // Merge spans which only differ in formatting not expressible in Markdown
+    let mut spans: Vec<([bool; 3], bool, String)> = Vec::new();
+    for span in parse(text) {
+        let markers = [span.style.bold, span.style.italic, span.style.strikethrough];
+        let monospace = span.style.monospace;
+        match spans.last_mut() {
+            Some(last) if last.0 == markers && last.1 == monospace => last.2.push_str(span.text),
+            _ => spans.push((markers, monospace, span.text.to_string())),
+        }
+    }
+
+    let mut markdown = String::with_capacity(text.len());
+    let mut stack: Vec<Marker> = Vec::new();
+    // Whitespace which is written after the delimiters closed before the next text
+    let mut pending = String::new();
+    for (index, (markers, monospace, text)) in spans.iter().enumerate() {
+        let core = text.trim();
+        if core.is_empty() {
+            pending.push_str(text);
+            continue;
+        }
+        let mut wanted = MARKERS
+            .iter()
+            .copied()
+            .filter(|marker| markers[*marker as usize])
+            .collect::<Vec<_>>();
+        // Close everything above the first marker not wanted anymore
+        if let Some(first) = stack.iter().position(|marker| !wanted.contains(marker)) {
+            for marker in stack.drain(first..).rev() {
+                markdown.push_str(marker.delimiter());
+            }
+        }
+        markdown.push_str(&pending);
+        markdown.push_str(&text[..text.len() - text.trim_start().len()]);
+        // Open the markers lasting longest first to avoid closing and reopening
+        let lasting = |marker: &Marker| {
+            spans[index..]
+                .iter()
+                .take_while(|(markers, _, _)| markers[*marker as usize])
+                .count()
+        };
+        wanted.sort_by_key(|marker| std::cmp::Reverse(lasting(marker)));
+        for marker in wanted {
+            if !stack.contains(&marker) {
+                markdown.push_str(marker.delimiter());
+                stack.push(marker);
+            }
+        }
+        if *monospace {
+            push_code_span(&mut markdown, core);
+        } else {
+            for ch in core.chars() {
+                if ESCAPED.contains(&ch) {
+                    markdown.push('\\');
+                }
+                markdown.push(ch);
+            }
+        }
+        pending = text[text.trim_end().len()..].to_string();
+    }
+    for marker in stack.drain(..).rev() {
+        markdown.push_str(marker.delimiter());
+    }
+    markdown.push_str(&pending);
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formatting::markdown::{from_irc, to_irc};
+    use crate::formatting::parse;
+
+    /// Returns the text with the formatting expressible in Markdown of all non-whitespace
+    /// characters.
+    fn formatting(irc: &str) -> Vec<(char, [bool; 4])> {
+        parse(irc)
+            .flat_map(|span| {
+                let style = span.style;
+                let markers = [
+                    style.bold,
+                    style.italic,
+                    style.strikethrough,
+                    style.monospace,
+                ];
+                // Formatting of whitespace at the start or end of spans isn't kept
+                span.text.chars().map(move |ch| {
+                    if ch.is_whitespace() {
+                        (ch, [false; 4])
+                    } else {
+                        (ch, markers)
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_to_irc() {
+        assert_eq!(
+            vec!["\x02bold\x02 \x02bold\x02 \x1ditalic\x1d \x1ditalic\x1d \x1estrike\x1e"],
+            to_irc("**bold** __bold__ *italic* _italic_ ~~strike~~")
+        );
+        assert_eq!(
+            vec!["\x02bold \x1dboth\x02\x1d \x02\x1dboth\x02\x1d"],
+            to_irc("**bold *both*** ***both***")
+        );
+        assert_eq!(vec!["\x11a *b*\x11"], to_irc("`a *b*`"));
+        assert_eq!(vec!["\x11`code`\x11"], to_irc("`` `code` ``"));
+    }
+
+    #[test]
+    fn test_to_irc_literals() {
+        assert_eq!(vec!["2 * 3 * 4"], to_irc("2 * 3 * 4"));
+        assert_eq!(vec!["snake_case_name"], to_irc("snake_case_name"));
+        assert_eq!(vec!["**not closed"], to_irc("**not closed"));
+        assert_eq!(vec!["`not closed"], to_irc("`not closed"));
+        assert_eq!(vec!["*escaped* \\"], to_irc("\\*escaped\\* \\\\"));
+        assert_eq!(vec!["\x02\\\x02"], to_irc("**\\\\**"));
+        assert_eq!(vec!["\x1e\\\x1e"], to_irc("~~\\\\~~"));
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        assert_eq!(
+            vec!["before", "\x11let a = 1;\x11", "", "\x11**a**\x11", "after"],
+            to_irc("before\n```rust\nlet a = 1;\n\n**a**\n```\nafter")
+        );
+    }
+
+    #[test]
+    fn test_from_irc() {
+        assert_eq!(
+            "**bold** *italic* ~~strike~~ `code`",
+            from_irc("\x02bold\x02 \x1ditalic\x1d \x1estrike\x1e \x11code\x11")
+        );
+        assert_eq!("***both** italic*", from_irc("\x1d\x02both\x02 italic\x1d"));
+        assert_eq!("**bold** *italic*", from_irc("\x02bold \x02\x1ditalic"));
+        assert_eq!("a\\*b\\_c\\~d\\`e\\\\", from_irc("a*b_c~d`e\\"));
+        assert_eq!(
+            "``a`b`` and `` `c ``",
+            from_irc("\x11a`b\x11 and \x11`c\x11")
+        );
+        // Colours, underline and reverse are dropped
+        assert_eq!("**a b**", from_irc("\x02\x034a\x03 \x1f\x16b"));
+    }
+
+    #[test]
+    fn test_round_trip_irc() {
+        for irc in &[
+            "\x02bold\x02 \x1ditalic\x1d \x1estrike\x1e \x11code\x11",
+            "\x02\x1d\x1e\x11all\x11\x1e\x1d\x02",
+            "\x02bold \x1dboth\x02 italic\x1d plain",
+            "\x02a\x02\x1db\x1d\x1ec\x1e",
+            "\x11a`b\x11 with *, _, ~ and \\",
+            "\x02\x11code in bold\x11 and text\x02",
+            "\x02\\\x02 \x1d\\\x1d \x1e\\\x1e",
+            "\x02\\\\\x02 \x1ea\\\x1e",
+        ] {
+            let markdown = from_irc(irc);
+            let lines = to_irc(&markdown);
+            assert_eq!(1, lines.len(), "{}", markdown);
+            assert_eq!(formatting(irc), formatting(&lines[0]), "{}", markdown);
+            // The Markdown is stable
+            assert_eq!(markdown, from_irc(&lines[0]));
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use crate::command::Command;
+use crate::errors::ParserError;
+use crate::message::Message;
+use crate::numeric::Numeric;
+use crate::prefix::Prefix;
+use crate::tokenizer::Tokenizer;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Byte range of a part of the message.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+struct Span {
+    start: u32,
+    end: u32,
+}
+
+impl Span {
+    /// Computes the range of `part` which has to be a subslice of `raw`.
+    fn of(raw: &str, part: &str) -> Self {
+        let start = part.as_ptr() as usize - raw.as_ptr() as usize;
+        Span {
+            start: start as u32,
+            end: (start + part.len()) as u32,
+        }
+    }
+
+    fn get(self, raw: &str) -> &str {
+        &raw[self.start as usize..self.end as usize]
+    }
+}
+
+/// A message which tokenizes its raw string once on creation and stores the byte ranges
+/// of all parts next to it.
+///
+/// In contrast to [Message], which tokenizes the string again on every accessor, all
+/// accessors of this type are O(1) (or linear in the number of tags or params for lookups).
+/// This makes it the better choice if multiple parts of the same message are accessed.
+///
+/// # Usage
+///
+/// ```rust
+/// use irc_rust::prefix::Prefix;
+/// use irc_rust::Message;
+/// # fn main() -> Result<(), irc_rust::errors::ParserError> {
+/// let message = Message::from("@key=value :name!user@host CMD param0 param1 :trailing")
+///     .indexed()?;
+/// assert_eq!(Some("value"), message.tag("key"));
+/// assert_eq!(Some(Prefix::new("name", Some("user"), Some("host"))), message.prefix());
+/// assert_eq!("CMD", message.command());
+/// assert_eq!(Some("param1"), message.param(1));
+/// assert_eq!(Some("trailing"), message.trailing());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct IndexedMessage {
+    raw: String,
+    tags: Vec<(Span, Span)>,
+    prefix: Option<(Span, Option<Span>, Option<Span>)>,
+    command: Span,
+    params: Vec<Span>,
+    trailing: Option<Span>,
+}
+
+impl IndexedMessage {
+    fn index(raw: String) -> Result<Self, ParserError> {
+        let (tags, prefix, command, params, trailing) = {
+            let raw = raw.as_str();
+            let mut tokenizer = Tokenizer::new(raw)?.tags();
+            let mut tags = Vec::new();
+            for res in tokenizer.as_iter() {
+                let (key, value) = res?;
+                tags.push((Span::of(raw, key), Span::of(raw, value)));
+            }
+            let mut tokenizer = tokenizer.prefix();
+            let prefix = tokenizer.parts()?.map(|prefix| {
+                (
+                    Span::of(raw, prefix.name()),
+                    prefix.user().map(|user| Span::of(raw, user)),
+                    prefix.host().map(|host| Span::of(raw, host)),
+                )
+            });
+            let mut tokenizer = tokenizer.command();
+            let command = Span::of(raw, tokenizer.command()?);
+            let mut tokenizer = tokenizer.params();
+            let params = tokenizer
+                .as_iter()
+                .map(|param| Span::of(raw, param))
+                .collect();
+            let trailing = tokenizer
+                .trailing()
+                .trailing()
+                .map(|trailing| Span::of(raw, trailing));
+            (tags, prefix, command, params, trailing)
+        };
+
+        Ok(IndexedMessage {
+            raw,
+            tags,
+            prefix,
+            command,
+            params,
+            trailing,
+        })
+    }
+
+    /// Returns all tags in the order of the message.
+    pub fn tags(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.tags
+            .iter()
+            .map(move |(key, value)| (key.get(&self.raw), value.get(&self.raw)))
+    }
+
+    /// Returns the raw value of the tag with the given key. For duplicate keys the last value
+    /// wins like in [Parsed](crate::parsed::Parsed).
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .rev()
+            .find(|(tag_key, _)| tag_key.get(&self.raw) == key)
+            .map(|(_, value)| value.get(&self.raw))
+    }
+
+    /// Returns the Prefix if present.
+    pub fn prefix(&self) -> Option<Prefix<'_>> {
+        self.prefix.map(|(name, user, host)| {
+            Prefix::new(
+                name.get(&self.raw),
+                user.map(|user| user.get(&self.raw)),
+                host.map(|host| host.get(&self.raw)),
+            )
+        })
+    }
+
+    /// Returns the command the message represents.
+    pub fn command(&self) -> &str {
+        self.command.get(&self.raw)
+    }
+
+    /// Returns the typed command the message represents.
+    pub fn typed_command(&self) -> Command<'_> {
+        Command::from(self.command())
+    }
+
+    /// Returns the numeric if the command is a three digit numeric reply.
+    pub fn numeric(&self) -> Option<Numeric> {
+        Numeric::parse(self.command())
+    }
+
+    /// Returns the params without the trailing parameter.
+    pub fn params(&self) -> impl Iterator<Item = &str> {
+        self.params.iter().map(move |param| param.get(&self.raw))
+    }
+
+    /// Returns the param at the given index.
+    pub fn param(&self, index: usize) -> Option<&str> {
+        self.params.get(index).map(|param| param.get(&self.raw))
+    }
+
+    /// Returns the trailing parameter if any is present.
+    pub fn trailing(&self) -> Option<&str> {
+        self.trailing.map(|trailing| trailing.get(&self.raw))
+    }
+
+    /// Returns the raw message.
+    pub fn as_str(&self) -> &str {
+        self.raw.as_str()
+    }
+
+    /// Drops the index and returns the plain message.
+    pub fn into_message(self) -> Message {
+        Message::from(self.raw)
+    }
+}
+
+impl Display for IndexedMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.raw.fmt(f)
+    }
+}
+
+impl FromStr for IndexedMessage {
+    type Err = ParserError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        IndexedMessage::index(s.to_string())
+    }
+}
+
+impl TryFrom<String> for IndexedMessage {
+    type Error = ParserError;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        IndexedMessage::index(raw)
+    }
+}
+
+impl TryFrom<Message> for IndexedMessage {
+    type Error = ParserError;
+
+    fn try_from(message: Message) -> Result<Self, Self::Error> {
+        message.indexed()
+    }
+}
+
+impl From<IndexedMessage> for Message {
+    fn from(message: IndexedMessage) -> Self {
+        message.into_message()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::{Component, ParserError, ParserErrorKind};
+    use crate::indexed::IndexedMessage;
+    use crate::prefix::Prefix;
+    use crate::Message;
+    use std::error::Error;
+
+    #[test]
+    fn test_all() -> Result<(), Box<dyn Error>> {
+        let message = "@key1=value1;key2=value2 :name!user@host CMD param0 param1 :trailing"
+            .parse::<IndexedMessage>()?;
+        assert_eq!(
+            vec![("key1", "value1"), ("key2", "value2")],
+            message.tags().collect::<Vec<_>>()
+        );
+        assert_eq!(Some("value2"), message.tag("key2"));
+        assert_eq!(None, message.tag("key3"));
+        assert_eq!(
+            Some(Prefix::new("name", Some("user"), Some("host"))),
+            message.prefix()
+        );
+        assert_eq!("CMD", message.command());
+        assert_eq!(
+            vec!["param0", "param1"],
+            message.params().collect::<Vec<_>>()
+        );
+        assert_eq!(Some("trailing"), message.trailing());
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_only() -> Result<(), Box<dyn Error>> {
+        let message = "CMD".parse::<IndexedMessage>()?;
+        assert_eq!(0, message.tags().count());
+        assert_eq!(None, message.prefix());
+        assert_eq!("CMD", message.command());
+        assert_eq!(None, message.param(0));
+        assert_eq!(None, message.trailing());
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_as_message() -> Result<(), Box<dyn Error>> {
+        let message = Message::from(":name@host 353 nick = #channel :nick1 nick2");
+        let indexed = message.clone().indexed()?;
+        assert_eq!(message.prefix()?, indexed.prefix());
+        assert_eq!(message.command()?, indexed.command());
+        assert_eq!(message.numeric()?, indexed.numeric());
+        assert_eq!(
+            message.params()?.collect::<Vec<_>>(),
+            indexed.params().collect::<Vec<_>>()
+        );
+        assert_eq!(message.trailing()?, indexed.trailing());
+        assert_eq!(message, Message::from(indexed));
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_tag() -> Result<(), Box<dyn Error>> {
+        let message = Message::from("@a=1;b=2;a=3 CMD");
+        let indexed = message.clone().indexed()?;
+        assert_eq!(Some("3"), indexed.tag("a"));
+        assert_eq!(message.parse()?.tag("a"), indexed.tag("a"));
+        assert_eq!(Some("2"), indexed.tag("b"));
+        assert_eq!(None, indexed.tag("c"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::NoCommand,
+                Component::Command,
+                0,
+                0
+            )),
+            "".parse::<IndexedMessage>()
+        );
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::NoTagValueEnd,
+                Component::Tags,
+                5,
+                5
+            )),
+            "@key=value".parse::<IndexedMessage>()
+        );
+    }
+}
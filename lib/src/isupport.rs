@@ -0,0 +1,387 @@
+//! Parameters advertised by the server with `RPL_ISUPPORT` (005).
+//!
+//! Servers send one or more `005` messages after the registration with tokens like
+//! `CASEMAPPING=ascii`, `EXCEPTS` or `-TOKEN` removing a previously advertised token.
+//! [ISupport] accumulates the tokens of all messages and provides typed getters returning
+//! the defaults of RFC 1459/2812 if a token hasn't been advertised. See the
+//! [specification](https://modern.ircdocs.horse/#rplisupport-parameters) for details.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::isupport::ISupport;
+//! use irc_rust::Message;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut isupport = ISupport::new();
+//! assert_eq!(9, isupport.nick_len());
+//!
+//! let message = Message::from(":irc.example.net 005 nick NICKLEN=30 PREFIX=(qov)~@+ NETWORK=Example\\x20Net :are supported by this server");
+//! isupport.add(&message.parse()?)?;
+//! assert_eq!(30, isupport.nick_len());
+//! assert_eq!(vec![('q', '~'), ('o', '@'), ('v', '+')], isupport.prefix());
+//! assert_eq!(Some("Example Net"), isupport.network());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::casemap::CaseMapping;
+use crate::command::Command;
+use crate::errors::ConversionError;
+use crate::numeric::Numeric;
+use crate::parsed::Parsed;
+use std::collections::HashMap;
+
+/// The type A, B, C and D channel modes advertised by `CHANMODES`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ChanModes<'a> {
+    /// Type A: Modes managing a list which always take a parameter like `b`.
+    pub list: &'a str,
+    /// Type B: Modes which always take a parameter like `k`.
+    pub always: &'a str,
+    /// Type C: Modes which only take a parameter when set like `l`.
+    pub when_set: &'a str,
+    /// Type D: Modes which never take a parameter like `m`.
+    pub never: &'a str,
+}
+
+/// Parameters of the server accumulated from `RPL_ISUPPORT` messages.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct ISupport {
+    tokens: HashMap<String, Option<String>>,
+}
+
+impl ISupport {
+    /// Creates an empty instance returning the defaults for all parameters.
+    pub fn new() -> Self {
+        ISupport::default()
+    }
+
+    /// Adds the tokens of an `RPL_ISUPPORT` message.
+    ///
+    /// The first param is the nickname of the client and the trailing param the human
+    /// readable `are supported by this server` so both are skipped.
+    pub fn add(&mut self, parsed: &Parsed) -> Result<(), ConversionError> {
+        let found = parsed.typed_command().ok_or(ConversionError::NoCommand)?;
+        if found != Command::Numeric(Numeric::RPL_ISUPPORT) {
+            return Err(ConversionError::WrongCommand {
+                expected: "005",
+                found: found.to_string(),
+            });
+        }
+        for token in parsed.params().flatten().skip(1) {
+            self.add_token(token);
+        }
+        Ok(())
+    }
+
+    /// Adds a single token like `NICKLEN=30`, `EXCEPTS` or `-EXCEPTS`.
+    ///
+    /// Escapes of the form `\xHH` in values are unescaped.
+    pub fn add_token(&mut self, token: &str) {
+        if let Some(name) = token.strip_prefix('-') {
+            self.tokens.remove(name);
+            return;
+        }
+        let (name, value) = match token.find('=') {
+            Some(index) => (&token[..index], Some(unescape(&token[index + 1..]))),
+            None => (token, None),
+        };
+        if !name.is_empty() {
+            self.tokens.insert(name.to_string(), value);
+        }
+    }
+
+    /// Returns true if the token has been advertised.
+    pub fn contains(&self, name: &str) -> bool {
+        self.tokens.contains_key(name)
+    }
+
+    /// Returns the unescaped value of a token. Tokens without a value return `None`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.tokens.get(name).and_then(|value| value.as_deref())
+    }
+
+    /// Returns an iterator over all advertised tokens and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.tokens
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_deref()))
+    }
+
+    fn number(&self, name: &str) -> Option<usize> {
+        self.get(name).and_then(|value| value.parse().ok())
+    }
+
+    /// `CASEMAPPING` defaults to `rfc1459`.
+    pub fn case_mapping_name(&self) -> &str {
+        self.get("CASEMAPPING").unwrap_or("rfc1459")
+    }
+
+    /// The casemapping advertised with `CASEMAPPING`. Unknown casemappings and the default
+    /// result in [CaseMapping::Rfc1459].
+    pub fn case_mapping(&self) -> CaseMapping {
+        self.case_mapping_name().parse().unwrap_or_default()
+    }
+
+    /// `CHANTYPES` defaults to `#&`.
+    pub fn chan_types(&self) -> &str {
+        match self.tokens.get("CHANTYPES") {
+            Some(value) => value.as_deref().unwrap_or(""),
+            None => "#&",
+        }
+    }
+
+    /// Returns true if **name** starts with one of the [ISupport::chan_types].
+    pub fn is_channel(&self, name: &str) -> bool {
+        name.chars()
+            .next()
+            .is_some_and(|first| self.chan_types().contains(first))
+    }
+
+    /// `PREFIX` as pairs of channel mode and prefix ordered from highest to lowest
+    /// privilege. Defaults to `(ov)@+`.
+    pub fn prefix(&self) -> Vec<(char, char)> {
+        let value = match self.tokens.get("PREFIX") {
+            Some(value) => value.as_deref().unwrap_or(""),
+            None => "(ov)@+",
+        };
+        value
+            .strip_prefix('(')
+            .and_then(|value| value.split_once(')'))
+            .map(|(modes, prefixes)| modes.chars().zip(prefixes.chars()).collect())
+            .unwrap_or_default()
+    }
+
+    /// `CHANMODES` defaults to `b,k,l,imnpst`. Missing types are empty.
+    pub fn chan_modes(&self) -> ChanModes<'_> {
+        let mut types = self.get("CHANMODES").unwrap_or("b,k,l,imnpst").split(',');
+        let mut next = || types.next().unwrap_or("");
+        ChanModes {
+            list: next(),
+            always: next(),
+            when_set: next(),
+            never: next(),
+        }
+    }
+
+    /// `NICKLEN` defaults to 9.
+    pub fn nick_len(&self) -> usize {
+        self.number("NICKLEN").unwrap_or(9)
+    }
+
+    /// `CHANNELLEN` defaults to 50.
+    pub fn channel_len(&self) -> usize {
+        self.number("CHANNELLEN").unwrap_or(50)
+    }
+
+    /// `TOPICLEN` if advertised.
+    pub fn topic_len(&self) -> Option<usize> {
+        self.number("TOPICLEN")
+    }
+
+    /// `KICKLEN` if advertised.
+    pub fn kick_len(&self) -> Option<usize> {
+        self.number("KICKLEN")
+    }
+
+    /// `AWAYLEN` if advertised.
+    pub fn away_len(&self) -> Option<usize> {
+        self.number("AWAYLEN")
+    }
+
+    /// Maximum number of modes with a parameter in one `MODE` message. `MODES` defaults to
+    /// 3. Returns `None` if `MODES` is advertised without a value which means unlimited.
+    pub fn modes(&self) -> Option<usize> {
+        match self.tokens.get("MODES") {
+            Some(value) => value.as_deref().and_then(|value| value.parse().ok()),
+            None => Some(3),
+        }
+    }
+
+    /// Maximum number of targets of **command** advertised by `TARGMAX`. Returns `None` if
+    /// the number isn't limited or not known.
+    pub fn max_targets(&self, command: &str) -> Option<usize> {
+        self.get("TARGMAX")?
+            .split(',')
+            .filter_map(|entry| entry.split_once(':'))
+            .find(|(name, _)| name.eq_ignore_ascii_case(command))
+            .and_then(|(_, max)| max.parse().ok())
+    }
+
+    /// `STATUSMSG` prefixes which can be prepended to channels to message only members
+    /// with that prefix. Defaults to none.
+    pub fn status_msg(&self) -> &str {
+        self.get("STATUSMSG").unwrap_or("")
+    }
+
+    /// The ban exception mode advertised by `EXCEPTS` which defaults to `e` if advertised
+    /// without a value.
+    pub fn excepts(&self) -> Option<char> {
+        self.mode_token("EXCEPTS", 'e')
+    }
+
+    /// The invite exception mode advertised by `INVEX` which defaults to `I` if advertised
+    /// without a value.
+    pub fn invex(&self) -> Option<char> {
+        self.mode_token("INVEX", 'I')
+    }
+
+    fn mode_token(&self, name: &str, default: char) -> Option<char> {
+        self.tokens
+            .get(name)
+            .map(|value| value.as_deref().and_then(|value| value.chars().next()))
+            .map(|mode| mode.unwrap_or(default))
+    }
+
+    /// True if the server only allows UTF-8 with `UTF8ONLY`.
+    pub fn utf8_only(&self) -> bool {
+        self.contains("UTF8ONLY")
+    }
+
+    /// The name of the network advertised by `NETWORK`.
+    pub fn network(&self) -> Option<&str> {
+        self.get("NETWORK")
+    }
+}
+
+/// Unescapes `\xHH` sequences. Invalid escapes are kept as they are.
+fn unescape(value: &str) -> String {
+    if !value.contains("\\x") {
+        return value.to_string();
+    }
+    let bytes = value.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index..index + 4)
+            .filter(|escape| escape.starts_with(b"\\x"))
+            .and_then(|escape| std::str::from_utf8(&escape[2..]).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                unescaped.push(byte);
+                index += 4;
+            }
+            None => {
+                unescaped.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::casemap::CaseMapping;
+    use crate::errors::ConversionError;
+    use crate::isupport::{unescape, ChanModes, ISupport};
+    use crate::Message;
+    use std::error::Error;
+
+    #[test]
+    fn test_defaults() {
+        let isupport = ISupport::new();
+        assert_eq!("rfc1459", isupport.case_mapping_name());
+        assert_eq!(CaseMapping::Rfc1459, isupport.case_mapping());
+        assert_eq!("#&", isupport.chan_types());
+        assert_eq!(vec![('o', '@'), ('v', '+')], isupport.prefix());
+        assert_eq!(
+            ChanModes {
+                list: "b",
+                always: "k",
+                when_set: "l",
+                never: "imnpst",
+            },
+            isupport.chan_modes()
+        );
+        assert_eq!(9, isupport.nick_len());
+        assert_eq!(50, isupport.channel_len());
+        assert_eq!(None, isupport.topic_len());
+        assert_eq!(Some(3), isupport.modes());
+        assert_eq!(None, isupport.max_targets("PRIVMSG"));
+        assert_eq!("", isupport.status_msg());
+        assert_eq!(None, isupport.excepts());
+        assert_eq!(None, isupport.invex());
+        assert!(!isupport.utf8_only());
+        assert_eq!(None, isupport.network());
+    }
+
+    #[test]
+    fn test_add() -> Result<(), Box<dyn Error>> {
+        let mut isupport = ISupport::new();
+        let lines = [
+            ":server 005 nick CASEMAPPING=ascii CHANTYPES=# PREFIX=(qaohv)~&@%+ CHANMODES=beI,k,l,imnpst :are supported by this server",
+            ":server 005 nick MODES TARGMAX=PRIVMSG:4,JOIN:,WHOIS:1 STATUSMSG=~&@%+ EXCEPTS INVEX=J UTF8ONLY TOPICLEN=390 :are supported by this server",
+        ];
+        for line in lines.iter() {
+            isupport.add(&Message::from(*line).parse()?)?;
+        }
+        assert_eq!("ascii", isupport.case_mapping_name());
+        assert_eq!(CaseMapping::Ascii, isupport.case_mapping());
+        assert_eq!("#", isupport.chan_types());
+        assert!(isupport.is_channel("#rust"));
+        assert!(!isupport.is_channel("&local"));
+        assert_eq!(5, isupport.prefix().len());
+        assert_eq!("beI", isupport.chan_modes().list);
+        assert_eq!(None, isupport.modes());
+        assert_eq!(Some(4), isupport.max_targets("privmsg"));
+        assert_eq!(None, isupport.max_targets("JOIN"));
+        assert_eq!(Some(1), isupport.max_targets("WHOIS"));
+        assert_eq!("~&@%+", isupport.status_msg());
+        assert_eq!(Some('e'), isupport.excepts());
+        assert_eq!(Some('J'), isupport.invex());
+        assert!(isupport.utf8_only());
+        assert_eq!(Some(390), isupport.topic_len());
+        assert_eq!(Some("ascii"), isupport.get("CASEMAPPING"));
+        assert!(isupport.contains("EXCEPTS"));
+        assert_eq!(None, isupport.get("EXCEPTS"));
+        assert_eq!(11, isupport.iter().count());
+        Ok(())
+    }
+
+    #[test]
+    fn test_negation() -> Result<(), Box<dyn Error>> {
+        let mut isupport = ISupport::new();
+        isupport
+            .add(&Message::from(":server 005 nick EXCEPTS NICKLEN=16 :are supported").parse()?)?;
+        isupport
+            .add(&Message::from(":server 005 nick -EXCEPTS -NICKLEN :are supported").parse()?)?;
+        assert_eq!(None, isupport.excepts());
+        assert_eq!(9, isupport.nick_len());
+        assert!(!isupport.contains("NICKLEN"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_values() {
+        let mut isupport = ISupport::new();
+        isupport.add_token("CHANTYPES=");
+        isupport.add_token("PREFIX=");
+        assert_eq!("", isupport.chan_types());
+        assert!(!isupport.is_channel("#channel"));
+        assert!(isupport.prefix().is_empty());
+    }
+
+    #[test]
+    fn test_wrong_command() -> Result<(), Box<dyn Error>> {
+        let mut isupport = ISupport::new();
+        assert_eq!(
+            Err(ConversionError::WrongCommand {
+                expected: "005",
+                found: "001".to_string()
+            }),
+            isupport.add(&Message::from(":server 001 nick :Welcome").parse()?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!("Example Net", unescape("Example\\x20Net"));
+        assert_eq!("a=b\\c", unescape("a\\x3Db\\x5Cc"));
+        assert_eq!("\\xZZ \\x2", unescape("\\xZZ\\x20\\x2"));
+        assert_eq!("ü", unescape("\\xC3\\xBC"));
+    }
+}
@@ -7,7 +7,7 @@
 //!
 //! - Ease the access to fields of the message without requiring the user to handle offsets and other IRC related things.
 //! - Minimize memory foodprint. For this goal the `Message` struct only owns the `String` of the actual message. Any
-//!     parts of the message and other structs only work on references of this string.
+//!   parts of the message and other structs only work on references of this string.
 //!
 //! Therefore this project expects the strings passed to the struct
 //! constructors to be valid parts of the IRC standard.
@@ -20,10 +20,37 @@
 //! Current support (as of version '0.3.*'):
 //!
 //! - **Message**: Create read-only Message from `String` or `&str` and with a builder `Message::builder()`.
-//! - **Tags**: access through the indexing operator and iterating over all tags.
-//! - **Prefix**: Read-only access + Builder.
+//!   `Message::indexed()` caches the positions of all parts for repeated access.
+//!   `Message::to_builder()` reproduces unchanged messages byte-exact and `Message::normalize()`
+//!   returns a canonical form for comparison.
+//! - **Raw Messages**: `RawMessage` and `RawTokenizer` parse lines which aren't valid UTF-8 byte-exact.
+//! - **Charsets**: Decoding with fallback to Latin-1, CP1252, ISO-8859-15 or KOI8-R configurable per
+//!   network and channel and encoding of built messages.
+//! - **Tags**: access through the indexing operator and iterating over all tags. Values can be
+//!   retrieved unescaped and are escaped by the builder. Tags keep the order of the message.
+//! - **Prefix**: Read-only access + Builder. Distinguishes server and user prefixes and can be
+//!   parsed and owned on its own.
+//! - **Masks**: Matching prefixes against `nick!user@host` masks with wildcards and CIDR ranges and
+//!   generating ban masks in the common styles.
+//! - **Command**: Typed and case-insensitive access to RFC 2812 and IRCv3 commands.
+//! - **Numerics**: Named constants and classification of numeric replies.
+//! - **Client**: Sans-IO state machine for registration, capability negotiation and `PING`s.
+//! - **ISUPPORT**: Typed access to the server parameters of `RPL_ISUPPORT` with RFC defaults.
+//! - **Modes**: Parsing channel and user mode changes as configured by `CHANMODES` and `PREFIX` and
+//!   packing them into as few `MODE` messages as `MODES` allows.
+//! - **Casemapping**: Comparing and hashing nicknames and channels by the server's `CASEMAPPING`.
+//! - **Typed Messages**: Zero-copy views on common commands like `PRIVMSG`, `JOIN` or `KICK`.
+//! - **Validation**: Strict validation against the RFC 2812 and IRCv3 grammar with `Message::validate()`.
+//! - **Formatting**: Parsing mIRC formatting codes into styled spans, stripping them and rendering
+//!   them as HTML or ANSI escape sequences with the 99 colour palette. Conversion from and to
+//!   Markdown for bridges.
+//! - **CTCP**: Parsing and building CTCP requests and replies including low-level and CTCP-level
+//!   quoting.
+//! - **DCC**: Typed `SEND`, `CHAT`, `RESUME` and `ACCEPT` offers including passive DCC and IPv6.
+//!   Blocking transfers with resume support with the `dcc` feature.
 //! - **Parameters List**: Read-only access, Iteration over elements, separate access to trailing parameter.
 //! - **Serde**: Serialization in any format supported by serde.
+//! - **Tokio**: `IrcCodec` framing lines into messages with the `tokio` feature.
 //!
 //! # Examples - for starters
 //!
@@ -65,11 +92,29 @@
 extern crate serde;
 
 pub mod builder;
+pub mod casemap;
+pub mod charset;
+pub mod client;
+#[cfg(feature = "tokio")]
+pub mod codec;
+pub mod command;
+pub mod ctcp;
+pub mod dcc;
 pub mod errors;
+pub mod formatting;
+pub mod indexed;
+pub mod isupport;
+pub mod mask;
 pub mod message;
+pub mod modes;
+pub mod numeric;
 pub mod parsed;
 pub mod prefix;
+pub mod raw;
+pub mod tags;
 pub mod tokenizer;
+pub mod typed;
+pub mod validate;
 
 #[cfg(test)]
 mod test;
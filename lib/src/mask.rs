@@ -0,0 +1,511 @@
+//! Hostmasks like `nick!user@host` with `*` and `?` wildcards as used in bans, ignore and
+//! access lists.
+//!
+//! Incomplete masks are completed the way servers do: `nick` becomes `nick!*@*`,
+//! `user@host` becomes `*!user@host` and `nick!user` becomes `nick!user@*`. The host may be
+//! a CIDR range like `192.168.0.0/16` or `2001:db8::/32` which matches IP hosts in it.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::casemap::CaseMapping;
+//! use irc_rust::mask::Mask;
+//! use irc_rust::Message;
+//! # fn main() -> Result<(), irc_rust::errors::ParserError> {
+//! let message = Message::from(":Nick[away]!~user@192.168.10.20 PRIVMSG #channel :Hello");
+//! let prefix = message.prefix()?.unwrap();
+//!
+//! assert!(Mask::new("nick{*}!*@*").matches(&prefix, CaseMapping::Rfc1459));
+//! assert!(Mask::new("*!*@192.168.0.0/16").matches(&prefix, CaseMapping::Rfc1459));
+//! assert!(!Mask::new("*!*@10.0.0.0/8").matches(&prefix, CaseMapping::Rfc1459));
+//! assert_eq!("*!~user@*", Mask::new("~user@*").to_string());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::builder::Builder;
+use crate::casemap::CaseMapping;
+use crate::command::Command;
+use crate::errors::BuildError;
+use crate::prefix::Prefix;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// The host part of a [Mask].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum HostMask {
+    /// A pattern with wildcards.
+    Glob(String),
+    /// A network address and the number of leading bits of the network.
+    Cidr(IpAddr, u8),
+}
+
+impl HostMask {
+    /// Parses a CIDR range like `10.0.0.0/8` or a glob pattern.
+    pub fn new(host: &str) -> Self {
+        host.split_once('/')
+            .and_then(|(address, bits)| {
+                let address = IpAddr::from_str(address).ok()?;
+                let bits = u8::from_str(bits).ok()?;
+                let max = if address.is_ipv4() { 32 } else { 128 };
+                if bits <= max {
+                    Some(HostMask::Cidr(address, bits))
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_else(|| HostMask::Glob(host.to_string()))
+    }
+
+    /// Returns true if the host matches. Only IP addresses match CIDR ranges.
+    pub fn matches(&self, host: &str, mapping: CaseMapping) -> bool {
+        match self {
+            HostMask::Glob(pattern) => glob_match(pattern, host, mapping),
+            HostMask::Cidr(network, bits) => IpAddr::from_str(host)
+                .map(|address| in_network(address, *network, *bits))
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl fmt::Display for HostMask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostMask::Glob(pattern) => f.write_str(pattern),
+            HostMask::Cidr(network, bits) => write!(f, "{}/{}", network, bits),
+        }
+    }
+}
+
+/// Returns true if **address** is in the network. IPv4-mapped IPv6 addresses match IPv4
+/// networks.
+fn in_network(address: IpAddr, network: IpAddr, bits: u8) -> bool {
+    match (address, network) {
+        (IpAddr::V4(address), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(bits)).unwrap_or(0);
+            u32::from(address) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(address), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(bits)).unwrap_or(0);
+            u128::from(address) & mask == u128::from(network) & mask
+        }
+        (IpAddr::V6(address), IpAddr::V4(_)) => address
+            .to_ipv4_mapped()
+            .is_some_and(|address| in_network(IpAddr::V4(address), network, bits)),
+        (IpAddr::V4(_), IpAddr::V6(_)) => false,
+    }
+}
+
+/// Styles of ban masks generated by [Mask::ban].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BanStyle {
+    /// `*!*@host`
+    Host,
+    /// `*!user@host`
+    UserHost,
+    /// `*!*user@*.domain` bans the user on any host of the domain. The ident prefix `~` is
+    /// replaced by `*`, the first label of host names is replaced by `*` and IP addresses
+    /// are collapsed to their /24 (IPv4) or /64 (IPv6) network.
+    UserDomain,
+    /// `nick!*@*`
+    Nick,
+}
+
+/// Number of network bits IP addresses are collapsed to with [BanStyle::UserDomain].
+const IPV4_NETWORK_BITS: u8 = 24;
+const IPV6_NETWORK_BITS: u8 = 64;
+
+/// Returns the host for [BanStyle::UserDomain].
+fn domain_mask(host: &str) -> HostMask {
+    match IpAddr::from_str(host) {
+        Ok(IpAddr::V4(address)) => {
+            let network = u32::from(address) & (u32::MAX << (32 - IPV4_NETWORK_BITS));
+            HostMask::Cidr(IpAddr::V4(Ipv4Addr::from(network)), IPV4_NETWORK_BITS)
+        }
+        Ok(IpAddr::V6(address)) => {
+            let network = u128::from(address) & (u128::MAX << (128 - IPV6_NETWORK_BITS));
+            HostMask::Cidr(IpAddr::V6(Ipv6Addr::from(network)), IPV6_NETWORK_BITS)
+        }
+        // Cloaks like `user/nick` don't contain a domain and are banned as they are
+        Err(_) if host.contains('/') => HostMask::Glob(host.to_string()),
+        // Only wildcard host names with a subdomain to not ban a whole top level domain
+        Err(_) => match host.split_once('.') {
+            Some((_, domain)) if domain.contains('.') => HostMask::Glob(format!("*.{}", domain)),
+            _ => HostMask::Glob(host.to_string()),
+        },
+    }
+}
+
+/// A `nick!user@host` pattern.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Mask {
+    pub nick: String,
+    pub user: String,
+    pub host: HostMask,
+}
+
+impl Mask {
+    /// Parses a mask completing missing parts with `*`.
+    pub fn new(mask: &str) -> Self {
+        let (rest, host) = match mask.rfind('@') {
+            Some(index) => (&mask[..index], Some(&mask[index + 1..])),
+            None => (mask, None),
+        };
+        let (nick, user) = match rest.find('!') {
+            Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+            // `user@host` without nick
+            None if host.is_some() => ("*", Some(rest)),
+            None => (rest, None),
+        };
+        let or_any = |part: Option<&str>| match part {
+            Some(part) if !part.is_empty() => part.to_string(),
+            _ => "*".to_string(),
+        };
+        Mask {
+            nick: or_any(Some(nick)),
+            user: or_any(user),
+            host: HostMask::new(&or_any(host)),
+        }
+    }
+
+    /// Generates a ban mask for the prefix in the given style.
+    ///
+    /// A missing user is replaced by `*`. Prefixes without host like nick-only or server
+    /// prefixes are banned by name with [BanStyle::Nick] in every style as a host based
+    /// mask would be `*!*@*`.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::mask::{BanStyle, Mask};
+    /// use irc_rust::prefix::Prefix;
+    ///
+    /// let prefix = Prefix::new("nick", Some("~user"), Some("dyn-10-1-2-3.isp.example.net"));
+    /// assert_eq!("*!*@dyn-10-1-2-3.isp.example.net", Mask::ban(&prefix, BanStyle::Host).to_string());
+    /// assert_eq!("*!~user@dyn-10-1-2-3.isp.example.net", Mask::ban(&prefix, BanStyle::UserHost).to_string());
+    /// assert_eq!("*!*user@*.isp.example.net", Mask::ban(&prefix, BanStyle::UserDomain).to_string());
+    /// assert_eq!("nick!*@*", Mask::ban(&prefix, BanStyle::Nick).to_string());
+    ///
+    /// let mask = Mask::ban(&Prefix::new("nick", Some("user"), Some("192.168.1.20")), BanStyle::UserDomain);
+    /// assert_eq!("*!*user@192.168.1.0/24", mask.to_string());
+    /// assert_eq!("MODE #channel +b *!*user@192.168.1.0/24", mask.ban_mode("#channel").build().to_string());
+    ///
+    /// let server = Prefix::new("irc.example.com", None, None);
+    /// assert_eq!("irc.example.com!*@*", Mask::ban(&server, BanStyle::Host).to_string());
+    /// ```
+    pub fn ban(prefix: &Prefix<'_>, style: BanStyle) -> Self {
+        let user = prefix.user().filter(|user| !user.is_empty()).unwrap_or("*");
+        let host = prefix.host().filter(|host| !host.is_empty());
+        let any = || "*".to_string();
+        match (style, host) {
+            (BanStyle::Host, Some(host)) => Mask {
+                nick: any(),
+                user: any(),
+                host: HostMask::Glob(host.to_string()),
+            },
+            (BanStyle::UserHost, Some(host)) => Mask {
+                nick: any(),
+                user: user.to_string(),
+                host: HostMask::Glob(host.to_string()),
+            },
+            (BanStyle::UserDomain, Some(host)) => Mask {
+                nick: any(),
+                user: format!("*{}", user.trim_start_matches(&['~', '*'][..])),
+                host: domain_mask(host),
+            },
+            (BanStyle::Nick, _) | (_, None) => Mask {
+                nick: prefix.name().to_string(),
+                user: any(),
+                host: HostMask::Glob(any()),
+            },
+        }
+    }
+
+    /// Creates a builder for `MODE <channel> +b <mask>`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if **channel** or the mask isn't a valid param. See [Mask::try_ban_mode] for
+    /// a non-panicking variant.
+    pub fn ban_mode<S: ToString>(&self, channel: S) -> Builder {
+        self.try_ban_mode(channel)
+            .unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Creates a builder for `MODE <channel> +b <mask>`.
+    ///
+    /// Fails if **channel** or the mask is empty, starts with ':' or contains spaces, CR,
+    /// LF or NUL.
+    pub fn try_ban_mode<S: ToString>(&self, channel: S) -> Result<Builder, BuildError> {
+        self.mode(channel, "+b")
+    }
+
+    /// Creates a builder for `MODE <channel> -b <mask>`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if **channel** or the mask isn't a valid param. See [Mask::try_unban_mode]
+    /// for a non-panicking variant.
+    pub fn unban_mode<S: ToString>(&self, channel: S) -> Builder {
+        self.try_unban_mode(channel)
+            .unwrap_or_else(|why| panic!("{}", why))
+    }
+
+    /// Creates a builder for `MODE <channel> -b <mask>`.
+    ///
+    /// Fails if **channel** or the mask is empty, starts with ':' or contains spaces, CR,
+    /// LF or NUL.
+    pub fn try_unban_mode<S: ToString>(&self, channel: S) -> Result<Builder, BuildError> {
+        self.mode(channel, "-b")
+    }
+
+    fn mode<S: ToString>(&self, channel: S, modes: &str) -> Result<Builder, BuildError> {
+        Builder::new(Command::Mode)
+            .try_param(channel)?
+            .try_param(modes)?
+            .try_param(self.to_string())
+    }
+
+    /// Returns true if the prefix matches the mask under the casemapping.
+    ///
+    /// Missing user and host parts of the prefix are treated as empty and only match `*`.
+    pub fn matches(&self, prefix: &Prefix<'_>, mapping: CaseMapping) -> bool {
+        glob_match(&self.nick, prefix.name(), mapping)
+            && glob_match(&self.user, prefix.user().unwrap_or_default(), mapping)
+            && self
+                .host
+                .matches(prefix.host().unwrap_or_default(), mapping)
+    }
+}
+
+impl From<&str> for Mask {
+    fn from(mask: &str) -> Self {
+        Mask::new(mask)
+    }
+}
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}!{}@{}", self.nick, self.user, self.host)
+    }
+}
+
+/// Matches **text** against a pattern where `*` matches any number of characters and `?`
+/// exactly one. Both are compared in their folded form.
+///
+/// # Usage
+///
+/// ```rust
+/// use irc_rust::casemap::CaseMapping;
+/// use irc_rust::mask::glob_match;
+///
+/// assert!(glob_match("*.Example.?om", "irc.example.com", CaseMapping::Ascii));
+/// assert!(!glob_match("*.example.com", "example.com", CaseMapping::Ascii));
+/// ```
+pub fn glob_match(pattern: &str, text: &str, mapping: CaseMapping) -> bool {
+    let pattern = mapping.fold(pattern).chars().collect::<Vec<_>>();
+    let text = mapping.fold(text).chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text position it matched up to
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(ch) if *ch == '?' || *ch == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` match one more character
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|ch| *ch == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::casemap::CaseMapping;
+    use crate::errors::BuildError;
+    use crate::mask::{glob_match, BanStyle, HostMask, Mask};
+    use crate::prefix::Prefix;
+    use crate::Message;
+    use std::error::Error;
+
+    #[test]
+    fn test_glob_match() {
+        let ascii = CaseMapping::Ascii;
+        assert!(glob_match("*", "", ascii));
+        assert!(glob_match("*", "anything", ascii));
+        assert!(glob_match("a*b*c", "aXXbYYc", ascii));
+        assert!(glob_match("a*b", "abab", ascii));
+        assert!(!glob_match("a*b", "abac", ascii));
+        assert!(glob_match("?ick", "Nick", ascii));
+        assert!(!glob_match("?ick", "ick", ascii));
+        assert!(glob_match("**?", "x", ascii));
+        assert!(!glob_match("", "x", ascii));
+        assert!(glob_match("nick[1]", "NICK{1}", CaseMapping::Rfc1459));
+        assert!(!glob_match("nick[1]", "NICK{1}", ascii));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("nick!*@*", Mask::new("nick").to_string());
+        assert_eq!("*!user@host", Mask::new("user@host").to_string());
+        assert_eq!("nick!user@*", Mask::new("nick!user").to_string());
+        assert_eq!("*!*@*", Mask::new("").to_string());
+        assert_eq!("*!*@*", Mask::new("!@").to_string());
+        assert_eq!(
+            HostMask::Cidr("10.0.0.0".parse().unwrap(), 8),
+            Mask::new("*!*@10.0.0.0/8").host
+        );
+        assert_eq!(
+            HostMask::Glob("10.0.0.0/33".to_string()),
+            Mask::new("*!*@10.0.0.0/33").host
+        );
+        assert_eq!(
+            HostMask::Cidr("2001:db8::".parse().unwrap(), 32),
+            Mask::new("*!*@2001:db8::/32").host
+        );
+        assert_eq!(
+            "*!*@2001:db8::/32",
+            Mask::new("*@2001:db8::/32").to_string()
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let mapping = CaseMapping::Rfc1459;
+        let prefix = Prefix::new("Nick", Some("~user"), Some("host.example.com"));
+        assert!(Mask::new("*!*@*.example.com").matches(&prefix, mapping));
+        assert!(Mask::new("nick!~USER@*").matches(&prefix, mapping));
+        assert!(Mask::new("Nick").matches(&prefix, mapping));
+        assert!(!Mask::new("Other").matches(&prefix, mapping));
+        assert!(!Mask::new("*!user@*").matches(&prefix, mapping));
+        assert!(!Mask::new("*!*@10.0.0.0/8").matches(&prefix, mapping));
+
+        let server = Prefix::new("irc.example.com", None, None);
+        assert!(Mask::new("irc.*").matches(&server, mapping));
+        assert!(!Mask::new("irc.*!?*@*").matches(&server, mapping));
+    }
+
+    #[test]
+    fn test_cidr() {
+        let mapping = CaseMapping::Rfc1459;
+        let matches = |mask: &str, host: &str| {
+            Mask::new(mask).matches(&Prefix::new("nick", Some("user"), Some(host)), mapping)
+        };
+        assert!(matches("*!*@192.168.0.0/16", "192.168.255.1"));
+        assert!(!matches("*!*@192.168.0.0/16", "192.169.0.1"));
+        assert!(matches("*!*@0.0.0.0/0", "8.8.8.8"));
+        assert!(matches("*!*@10.1.2.3/32", "10.1.2.3"));
+        assert!(matches("*!*@2001:db8::/32", "2001:db8:1::5"));
+        assert!(!matches("*!*@2001:db8::/32", "2001:db9::5"));
+        assert!(matches("*!*@10.0.0.0/8", "::ffff:10.2.3.4"));
+        assert!(!matches("*!*@::/0", "10.2.3.4"));
+        assert!(!matches("*!*@10.0.0.0/8", "10.example.com"));
+        // IPv6 hosts with globs
+        assert!(matches("*!*@2001:db8:*", "2001:db8::1"));
+    }
+
+    #[test]
+    fn test_ban() {
+        let ban = |host: &str| {
+            Mask::ban(
+                &Prefix::new("nick", Some("~user"), Some(host)),
+                BanStyle::UserDomain,
+            )
+            .to_string()
+        };
+        assert_eq!("*!*user@*.example.com", ban("host.example.com"));
+        assert_eq!("*!*user@example.com", ban("example.com"));
+        assert_eq!("*!*user@localhost", ban("localhost"));
+        assert_eq!("*!*user@10.1.2.0/24", ban("10.1.2.3"));
+        assert_eq!("*!*user@2001:db8:1:2::/64", ban("2001:db8:1:2:3:4:5:6"));
+        // Cloaks
+        assert_eq!("*!*user@user/nick", ban("user/nick"));
+        assert_eq!(
+            "*!*user@gateway/web/irccloud.com/x-abc",
+            ban("gateway/web/irccloud.com/x-abc")
+        );
+        assert_eq!("*!*user@*.5678EF.IP", ban("ABCD1234.5678EF.IP"));
+
+        let prefix = Prefix::new("server.example.com", None, None);
+        for style in [BanStyle::Host, BanStyle::UserHost, BanStyle::UserDomain].iter() {
+            assert_eq!(
+                "server.example.com!*@*",
+                Mask::ban(&prefix, *style).to_string()
+            );
+        }
+        let prefix = Prefix::new("nick", Some("user"), Some(""));
+        assert_eq!("nick!*@*", Mask::ban(&prefix, BanStyle::Host).to_string());
+        let prefix = Prefix::new("nick", None, Some("host"));
+        assert_eq!(
+            "*!*@host",
+            Mask::ban(&prefix, BanStyle::UserHost).to_string()
+        );
+    }
+
+    #[test]
+    fn test_ban_mode_invalid() {
+        let mask = Mask::new("nick!user@host");
+        assert_eq!(
+            Err(BuildError::InvalidCharacter {
+                part: "param",
+                character: ' '
+            }),
+            mask.try_ban_mode("#a #b")
+        );
+        assert_eq!(
+            Err(BuildError::ParamStartsWithColon),
+            mask.try_unban_mode(":#a")
+        );
+        assert_eq!(
+            Err(BuildError::InvalidCharacter {
+                part: "param",
+                character: ' '
+            }),
+            Mask::new("a b").try_ban_mode("#a")
+        );
+    }
+
+    #[test]
+    fn test_ban_matches() -> Result<(), Box<dyn Error>> {
+        let message = Message::from(":Nick!~user@192.168.1.20 JOIN #channel");
+        let prefix = message.prefix()?.unwrap();
+        for style in [
+            BanStyle::Host,
+            BanStyle::UserHost,
+            BanStyle::UserDomain,
+            BanStyle::Nick,
+        ]
+        .iter()
+        {
+            let mask = Mask::ban(&prefix, *style);
+            assert!(mask.matches(&prefix, CaseMapping::Rfc1459), "{}", mask);
+            // Generated masks parse to the same mask
+            assert_eq!(mask, Mask::new(&mask.to_string()));
+        }
+        assert!(Mask::ban(&prefix, BanStyle::UserDomain).matches(
+            &Prefix::new("Other", Some("user"), Some("192.168.1.99")),
+            CaseMapping::Rfc1459
+        ));
+
+        let mask = Mask::ban(&prefix, BanStyle::Host);
+        assert_eq!(
+            "MODE #channel -b *!*@192.168.1.20",
+            mask.unban_mode("#channel").build().to_string()
+        );
+        Ok(())
+    }
+}
@@ -2,10 +2,16 @@ use std::fmt;
 use std::fmt::{Display, Formatter};
 
 use crate::builder::Builder as MessageBuilder;
+use crate::command::Command;
 use crate::errors::ParserError;
+use crate::indexed::IndexedMessage;
+use crate::numeric::Numeric;
 use crate::parsed::Parsed;
 use crate::prefix::Prefix;
+use crate::tags;
 use crate::tokenizer::{PartialCfg, Start, Tokenizer};
+use crate::validate;
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::str::FromStr;
 
@@ -44,7 +50,7 @@ pub struct Message {
 
 impl Message {
     /// Returns a fully parsed but zero-copy struct referencing the parsed message.
-    pub fn parse(&self) -> Result<Parsed, ParserError> {
+    pub fn parse(&self) -> Result<Parsed<'_>, ParserError> {
         Parsed::try_from(self.raw.as_str())
     }
 
@@ -78,22 +84,69 @@ impl Message {
         Tokenizer::new(self.raw.as_str())?.parse_partial(cfg)
     }
 
+    /// Tokenizes the message once and returns a message caching the positions of its parts.
+    /// Prefer this over calling multiple accessors on [Message] as these tokenize the message
+    /// on every call.
+    pub fn indexed(self) -> Result<IndexedMessage, ParserError> {
+        IndexedMessage::try_from(self.raw)
+    }
+
     /// Returns a tokenizer over the message. Can be used to implement a custom parsing algorithm.
-    pub fn tokenizer(&self) -> Result<Tokenizer<Start>, ParserError> {
+    pub fn tokenizer(&self) -> Result<Tokenizer<'_, Start>, ParserError> {
         Tokenizer::new(self.raw.as_str())
     }
 
     /// Creates a message builder as alternative to building an irc string before creating the message.
-    pub fn builder(command: &str) -> MessageBuilder {
+    pub fn builder<S: ToString>(command: S) -> MessageBuilder {
         MessageBuilder::new(command)
     }
 
     /// Creates a builder from this message. Only initializes fields already present in the message.
     /// By using this method a whole new Message will be created.
+    ///
+    /// Building the unchanged builder reproduces the message byte-exact including the order
+    /// and escaping of tags, tags without value and the case of the command. Only repeated
+    /// spaces between params and duplicate tags, of which the last value is kept, are lost.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::Message;
+    /// # fn main() -> Result<(), irc_rust::errors::ParserError> {
+    /// let message = Message::from("@b=1;a;c= :nick!user@host privmsg #channel :Hi");
+    /// assert_eq!(message, message.to_builder()?.build());
+    /// # Ok(())
+    /// # }
+    /// ```
     pub fn to_builder(&self) -> Result<MessageBuilder, ParserError> {
         MessageBuilder::from_str(self.raw.as_str())
     }
 
+    /// Returns the canonical form of this message to compare or deduplicate messages which
+    /// differ only in their formatting.
+    ///
+    /// - Tags are sorted by key and empty values are removed as they are equal to missing ones.
+    /// - Tag values are escaped canonically, e.g. `\b` becomes `b`.
+    /// - The command is uppercased.
+    /// - The colon of the trailing param is removed if it isn't needed.
+    /// - Params are separated by single spaces.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::Message;
+    /// # fn main() -> Result<(), irc_rust::errors::ParserError> {
+    /// let first = Message::from("@time=1;account= :nick privmsg  #channel :Hi");
+    /// let second = Message::from("@account;time=1 :nick PRIVMSG #channel Hi");
+    /// assert_eq!("@account;time=1 :nick PRIVMSG #channel Hi", first.normalize()?.to_string());
+    /// assert_eq!(first.normalize()?, second.normalize()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn normalize(&self) -> Result<Message, ParserError> {
+        self.to_builder().map(|builder| builder.normalize().build())
+    }
+
     /// Returns tags if any are present.
     pub fn tags(
         &self,
@@ -101,8 +154,29 @@ impl Message {
         Tokenizer::new(self.raw.as_str()).map(|tokenizer| tokenizer.tags().into_iter())
     }
 
+    /// Returns tags if any are present with their values unescaped.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::Message;
+    /// # fn main() -> Result<(), irc_rust::errors::ParserError> {
+    /// let message = Message::from("@key=a\\sb CMD");
+    /// let (key, value) = message.tags_unescaped()?.next().unwrap()?;
+    /// assert_eq!("key", key);
+    /// assert_eq!("a b", value);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tags_unescaped(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<(&str, Cow<'_, str>), ParserError>>, ParserError> {
+        self.tags()
+            .map(|tags| tags.map(|res| res.map(|(key, value)| (key, tags::unescape(value)))))
+    }
+
     /// Returns the Prefix if present.
-    pub fn prefix(&self) -> Result<Option<Prefix>, ParserError> {
+    pub fn prefix(&self) -> Result<Option<Prefix<'_>>, ParserError> {
         Tokenizer::new(self.raw.as_str()).and_then(|tokenizer| tokenizer.prefix().parts())
     }
 
@@ -111,6 +185,16 @@ impl Message {
         Tokenizer::new(self.raw.as_str()).and_then(|tokenizer| tokenizer.command().command())
     }
 
+    /// Returns the typed command the message represents.
+    pub fn typed_command(&self) -> Result<Command<'_>, ParserError> {
+        self.command().map(Command::from)
+    }
+
+    /// Returns the numeric if the command is a three digit numeric reply.
+    pub fn numeric(&self) -> Result<Option<Numeric>, ParserError> {
+        self.command().map(Numeric::parse)
+    }
+
     /// Returns the params if any are present.
     pub fn params(&self) -> Result<impl Iterator<Item = &str>, ParserError> {
         Tokenizer::new(self.raw.as_str()).map(|tokenizer| tokenizer.params().into_iter())
@@ -120,6 +204,13 @@ impl Message {
     pub fn trailing(&self) -> Result<Option<&str>, ParserError> {
         Tokenizer::new(self.raw.as_str()).map(|tokenizer| tokenizer.trailing().trailing())
     }
+
+    /// Strictly validates the message against the RFC 2812 and IRCv3 grammar.
+    ///
+    /// Returns all violations with their byte span. See [validate](crate::validate) for details.
+    pub fn validate(&self) -> Result<(), Vec<ParserError>> {
+        validate::validate(self.raw.as_str())
+    }
 }
 
 impl Display for Message {
@@ -152,6 +243,8 @@ impl From<&str> for Message {
 
 #[cfg(test)]
 mod tests {
+    use crate::builder::Builder;
+    use crate::errors::{Component, ParserError, ParserErrorKind};
     use crate::message::Message;
 
     #[test]
@@ -171,11 +264,79 @@ mod tests {
             Message::from("@test=test :user@prefix!host COMMAND param :trailing".to_string());
         let tags = message.tags();
         assert!(tags.is_ok(), "{:?}", tags.err());
-        let mut tags = tags.unwrap().into_iter();
+        let mut tags = tags.unwrap();
         let tag = tags.next();
         assert!(tag.is_some(), "{:?}", tag);
     }
 
+    #[test]
+    fn test_round_trip() -> Result<(), ParserError> {
+        for raw in &[
+            "CMD",
+            "cmd param",
+            "CMD param :",
+            "CMD param :trailing",
+            "CMD :trailing with spaces",
+            "@z=1;a;m=;k=\\b\\ CMD",
+            "@+draft/typing=active;msgid=abc :nick!user@host TAGMSG #channel",
+            ":irc.example.net 001 nick :Welcome to the network",
+        ] {
+            let message = Message::from(*raw);
+            assert_eq!(*raw, message.to_builder()?.build().to_string());
+        }
+        // Repeated spaces are collapsed
+        let message = Message::from("CMD  a   b");
+        assert_eq!("CMD a b", message.to_builder()?.build().to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize() -> Result<(), ParserError> {
+        let normalize = |raw: &str| Message::from(raw).normalize().map(|m| m.to_string());
+        assert_eq!("@a;b=2;c=1 CMD x", normalize("@c=1;a=;b=2 cmd x")?);
+        assert_eq!("CMD x y", normalize("CMD  x :y")?);
+        assert_eq!("CMD x :", normalize("CMD x :")?);
+        assert_eq!("CMD x ::y", normalize("CMD x ::y")?);
+        assert_eq!("CMD x :y z", normalize("CMD x :y z")?);
+        // Tag values are compared unescaped
+        assert_eq!(normalize("@a=b CMD")?, normalize("@a=b\\ CMD")?);
+        assert_eq!(normalize("@a=b CMD")?, normalize("@a=\\b CMD")?);
+        assert_eq!("@a CMD", normalize("@a=\\ CMD")?);
+        assert_eq!("@a=b\\sc\\\\ CMD", normalize("@a=b\\sc\\\\ CMD")?);
+        let normalized = Message::from("CMD x y").normalize()?;
+        assert_eq!(normalized, normalized.normalize()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_builder_invalid() {
+        let error = |kind, component, offset, length| {
+            Err(ParserError::new(kind, component, offset, length))
+        };
+        assert_eq!(
+            error(ParserErrorKind::InvalidCharacter, Component::Params, 13, 1),
+            Message::from("PRIVMSG #c :a\0b").normalize()
+        );
+        assert_eq!(
+            error(ParserErrorKind::InvalidPrefixUser, Component::Prefix, 6, 4),
+            Message::from(":nick!user CMD")
+                .to_builder()
+                .map(Builder::build)
+        );
+        assert_eq!(
+            error(ParserErrorKind::InvalidPrefixUser, Component::Prefix, 6, 0),
+            Message::from(":nick!@host CMD").normalize()
+        );
+        assert_eq!(
+            error(ParserErrorKind::InvalidPrefixHost, Component::Prefix, 6, 0),
+            Message::from(":nick@ CMD").normalize()
+        );
+        assert_eq!(
+            error(ParserErrorKind::InvalidCharacter, Component::Prefix, 3, 1),
+            Message::from(":ni\0ck CMD").normalize()
+        );
+    }
+
     #[test]
     fn test_prefix() {
         let message =
@@ -0,0 +1,462 @@
+//! Parsing and building of channel and user mode changes.
+//!
+//! Which channel modes take an argument depends on the server and is advertised with the
+//! `CHANMODES` and `PREFIX` tokens of [ISupport]:
+//!
+//! - Prefix modes like `o` and type A modes like `b` always take an argument. Type A modes
+//!   without argument request the list.
+//! - Type B modes like `k` always take an argument.
+//! - Type C modes like `l` only take an argument when set.
+//! - Type D modes like `m` and unknown modes never take an argument.
+//!
+//! User modes never take arguments.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::isupport::ISupport;
+//! use irc_rust::modes::{pack, ModeChange, Modes};
+//! use irc_rust::Message;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let isupport = ISupport::new();
+//! let message = Message::from(":op!user@host MODE #channel +ov-k nick1 nick2 key");
+//! let modes = Modes::parse(&message.parse()?, &isupport)?;
+//! assert_eq!("#channel", modes.target);
+//! assert_eq!(
+//!     vec![
+//!         ModeChange { add: true, mode: 'o', arg: Some("nick1") },
+//!         ModeChange { add: true, mode: 'v', arg: Some("nick2") },
+//!         ModeChange { add: false, mode: 'k', arg: Some("key") },
+//!     ],
+//!     modes.changes
+//! );
+//!
+//! // The default MODES limit is 3 modes with arguments per line
+//! let voices = ["a", "b", "c", "d"]
+//!     .iter()
+//!     .map(|nick| ModeChange { add: true, mode: 'v', arg: Some(*nick) })
+//!     .collect::<Vec<_>>();
+//! let lines = pack("#channel", &voices, &isupport)?;
+//! assert_eq!("MODE #channel +vvv a b c", lines[0].to_string());
+//! assert_eq!("MODE #channel +v d", lines[1].to_string());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::builder;
+use crate::command::Command;
+use crate::errors::{BuildError, ConversionError};
+use crate::isupport::ISupport;
+use crate::numeric::Numeric;
+use crate::parsed::Parsed;
+use crate::Message;
+use std::fmt;
+
+/// Maximum length of a message without tags and line ending.
+const MAX_LINE_LENGTH: usize = 510;
+/// Maximum number of mode arguments as target and modes take two of the 15 params.
+const MAX_ARGS: usize = 13;
+
+/// A single mode being set or unset.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ModeChange<'a> {
+    /// True if the mode is set with `+`, false if unset with `-`.
+    pub add: bool,
+    pub mode: char,
+    pub arg: Option<&'a str>,
+}
+
+impl<'a> fmt::Display for ModeChange<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.add { '+' } else { '-' };
+        match self.arg {
+            Some(arg) => write!(f, "{}{} {}", sign, self.mode, arg),
+            None => write!(f, "{}{}", sign, self.mode),
+        }
+    }
+}
+
+/// The mode changes of a `MODE` message or the current modes of `RPL_CHANNELMODEIS` (324).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Modes<'a> {
+    /// The channel or nickname the modes apply to.
+    pub target: &'a str,
+    pub changes: Vec<ModeChange<'a>>,
+}
+
+impl<'a> Modes<'a> {
+    /// Parses the modes of a `MODE` or `RPL_CHANNELMODEIS` message.
+    ///
+    /// Targets starting with one of the `CHANTYPES` are parsed as channel modes, others as
+    /// user modes.
+    pub fn parse(parsed: &Parsed<'a>, isupport: &ISupport) -> Result<Self, ConversionError> {
+        let command = parsed.typed_command().ok_or(ConversionError::NoCommand)?;
+        let mut params = parsed
+            .params()
+            .flatten()
+            .copied()
+            .chain(parsed.trailing())
+            .collect::<Vec<_>>()
+            .into_iter();
+        let name = match command {
+            Command::Mode => "MODE",
+            Command::Numeric(Numeric::RPL_CHANNELMODEIS) => {
+                // Skip the nickname of the client
+                params.next();
+                "324"
+            }
+            found => {
+                return Err(ConversionError::WrongCommand {
+                    expected: "MODE",
+                    found: found.to_string(),
+                })
+            }
+        };
+        let target = params.next().ok_or(ConversionError::MissingParam {
+            command: name,
+            index: 0,
+            name: "target",
+        })?;
+        let changes = match params.next() {
+            Some(modes) if isupport.is_channel(target) => {
+                parse_channel_modes(modes, params, isupport)
+            }
+            Some(modes) => parse_user_modes(modes),
+            // Querying the modes
+            None => Vec::new(),
+        };
+        Ok(Modes { target, changes })
+    }
+}
+
+/// Parses channel modes like `+ov-k` and pairs them with **args** as configured by
+/// `CHANMODES` and `PREFIX`. Modes missing their argument have none.
+pub fn parse_channel_modes<'a, I>(modes: &str, args: I, isupport: &ISupport) -> Vec<ModeChange<'a>>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let chan_modes = isupport.chan_modes();
+    let prefix = isupport.prefix();
+    let mut args = args.into_iter();
+    parse_signed(modes)
+        .map(|(add, mode)| {
+            let takes_arg = prefix.iter().any(|(prefix_mode, _)| *prefix_mode == mode)
+                || chan_modes.list.contains(mode)
+                || chan_modes.always.contains(mode)
+                || (add && chan_modes.when_set.contains(mode));
+            ModeChange {
+                add,
+                mode,
+                arg: if takes_arg { args.next() } else { None },
+            }
+        })
+        .collect()
+}
+
+/// Parses user modes like `+iw-x`.
+pub fn parse_user_modes<'a>(modes: &str) -> Vec<ModeChange<'a>> {
+    parse_signed(modes)
+        .map(|(add, mode)| ModeChange {
+            add,
+            mode,
+            arg: None,
+        })
+        .collect()
+}
+
+/// Returns the modes with their sign. Modes before the first sign are added.
+fn parse_signed(modes: &str) -> impl Iterator<Item = (bool, char)> + '_ {
+    let mut add = true;
+    modes.chars().filter_map(move |ch| match ch {
+        '+' => {
+            add = true;
+            None
+        }
+        '-' => {
+            add = false;
+            None
+        }
+        _ => Some((add, ch)),
+    })
+}
+
+/// Packs mode changes into as few `MODE` messages for **target** as possible.
+///
+/// Each message contains at most as many changes with an argument as allowed by `MODES`
+/// but at least one and doesn't exceed 510 bytes. The order of the changes is kept.
+///
+/// An argument which is empty, contains a space or starts with a `:` can only be sent as
+/// trailing param and therefore ends its message.
+///
+/// Fails if **target** isn't a valid param or an argument contains CR, LF or NUL.
+pub fn pack(
+    target: &str,
+    changes: &[ModeChange],
+    isupport: &ISupport,
+) -> Result<Vec<Message>, BuildError> {
+    let limit = isupport
+        .modes()
+        .map_or(MAX_ARGS, |limit| limit.clamp(1, MAX_ARGS));
+    let mut messages = Vec::new();
+    let mut line: Vec<&ModeChange> = Vec::new();
+    let mut length = 0;
+    let mut args = 0;
+    // "MODE <target> "
+    let base = 6 + target.len();
+    for change in changes {
+        let has_arg = change.arg.is_some();
+        let trailing = change.arg.is_some_and(builder::needs_trailing);
+        // Mode, sign and argument with the leading ':' of a trailing argument
+        let added = 2 + change.arg.map_or(0, |arg| arg.len() + 1) + usize::from(trailing);
+        let full = (has_arg && args >= limit) || base + length + added > MAX_LINE_LENGTH;
+        if full && !line.is_empty() {
+            messages.push(mode_message(target, &line)?);
+            line.clear();
+            length = 0;
+            args = 0;
+        }
+        line.push(change);
+        length += added;
+        if has_arg {
+            args += 1;
+        }
+        if trailing {
+            messages.push(mode_message(target, &line)?);
+            line.clear();
+            length = 0;
+            args = 0;
+        }
+    }
+    if !line.is_empty() {
+        messages.push(mode_message(target, &line)?);
+    }
+    Ok(messages)
+}
+
+fn mode_message(target: &str, changes: &[&ModeChange]) -> Result<Message, BuildError> {
+    let mut modes = String::new();
+    let mut sign = None;
+    for change in changes {
+        if sign != Some(change.add) {
+            modes.push(if change.add { '+' } else { '-' });
+            sign = Some(change.add);
+        }
+        modes.push(change.mode);
+    }
+    let mut builder = Message::builder(Command::Mode)
+        .try_param(target)?
+        .try_param(modes)?;
+    for arg in changes.iter().filter_map(|change| change.arg) {
+        builder = if builder::needs_trailing(arg) {
+            builder.try_trailing(arg)?
+        } else {
+            builder.try_param(arg)?
+        };
+    }
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::{BuildError, ConversionError};
+    use crate::isupport::ISupport;
+    use crate::modes::{pack, parse_channel_modes, parse_user_modes, ModeChange, Modes};
+    use crate::Message;
+    use std::error::Error;
+
+    fn change(add: bool, mode: char, arg: Option<&str>) -> ModeChange<'_> {
+        ModeChange { add, mode, arg }
+    }
+
+    fn lines(messages: Vec<Message>) -> Vec<String> {
+        messages.iter().map(|message| message.to_string()).collect()
+    }
+
+    #[test]
+    fn test_channel_modes() {
+        let mut isupport = ISupport::new();
+        isupport.add_token("CHANMODES=beI,k,l,imnpst");
+        isupport.add_token("PREFIX=(qaohv)~&@%+");
+        assert_eq!(
+            vec![
+                change(true, 'q', Some("owner")),
+                change(true, 'l', Some("10")),
+                change(true, 'm', None),
+                change(false, 'l', None),
+                change(false, 'k', Some("key")),
+                change(false, 'b', Some("*!*@host")),
+                change(true, 'I', None),
+            ],
+            parse_channel_modes(
+                "+qlm-lkb+I",
+                vec!["owner", "10", "key", "*!*@host"],
+                &isupport
+            )
+        );
+        // Unknown modes don't take arguments
+        assert_eq!(
+            vec![change(true, 'X', None), change(true, 'o', Some("nick"))],
+            parse_channel_modes("X+o", vec!["nick"], &isupport)
+        );
+    }
+
+    #[test]
+    fn test_user_modes() {
+        assert_eq!(
+            vec![
+                change(true, 'i', None),
+                change(true, 'w', None),
+                change(false, 'x', None)
+            ],
+            parse_user_modes("+iw-x")
+        );
+    }
+
+    #[test]
+    fn test_parse() -> Result<(), Box<dyn Error>> {
+        let isupport = ISupport::new();
+        let message = Message::from(":nick MODE nick :+i-w");
+        let modes = Modes::parse(&message.parse()?, &isupport)?;
+        assert_eq!("nick", modes.target);
+        assert_eq!(
+            vec![change(true, 'i', None), change(false, 'w', None)],
+            modes.changes
+        );
+
+        let message = Message::from(":srv 324 me #channel +ntlk 10 :secret");
+        let modes = Modes::parse(&message.parse()?, &isupport)?;
+        assert_eq!("#channel", modes.target);
+        assert_eq!(
+            vec![
+                change(true, 'n', None),
+                change(true, 't', None),
+                change(true, 'l', Some("10")),
+                change(true, 'k', Some("secret"))
+            ],
+            modes.changes
+        );
+
+        let message = Message::from("MODE #channel");
+        assert!(Modes::parse(&message.parse()?, &isupport)?
+            .changes
+            .is_empty());
+
+        let message = Message::from("MODE");
+        assert_eq!(
+            Err(ConversionError::MissingParam {
+                command: "MODE",
+                index: 0,
+                name: "target"
+            }),
+            Modes::parse(&message.parse()?, &isupport)
+        );
+        let message = Message::from("JOIN #channel");
+        assert!(matches!(
+            Modes::parse(&message.parse()?, &isupport),
+            Err(ConversionError::WrongCommand { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pack() -> Result<(), Box<dyn Error>> {
+        let isupport = ISupport::new();
+        let changes = vec![
+            change(true, 'o', Some("a")),
+            change(true, 'm', None),
+            change(false, 'v', Some("b")),
+            change(false, 'n', None),
+            change(true, 'k', Some("key")),
+            change(true, 'l', Some("5")),
+            change(true, 't', None),
+        ];
+        assert_eq!(
+            vec!["MODE #c +om-vn+k a b key", "MODE #c +lt 5"],
+            lines(pack("#c", &changes, &isupport)?)
+        );
+
+        let mut unlimited = ISupport::new();
+        unlimited.add_token("MODES");
+        assert_eq!(1, pack("#c", &changes, &unlimited)?.len());
+        assert!(pack("#c", &[], &isupport)?.is_empty());
+
+        let mut zero = ISupport::new();
+        zero.add_token("MODES=0");
+        assert_eq!(
+            vec!["MODE #c +om a", "MODE #c -v b"],
+            lines(pack("#c", &changes[..3], &zero)?)
+        );
+
+        let voices = vec![change(true, 'v', Some("a")); 14];
+        let lines = pack("#c", &voices, &unlimited)?;
+        assert_eq!(2, lines.len());
+        assert_eq!(15, lines[0].params()?.count());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pack_trailing() -> Result<(), Box<dyn Error>> {
+        let isupport = ISupport::new();
+        let changes = vec![
+            change(true, 'k', Some("")),
+            change(true, 'o', Some("a")),
+            change(true, 'k', Some("a b")),
+            change(true, 'v', Some(":b")),
+            change(true, 'm', None),
+        ];
+        assert_eq!(
+            vec![
+                "MODE #c +k :",
+                "MODE #c +ok a :a b",
+                "MODE #c +v ::b",
+                "MODE #c +m"
+            ],
+            lines(pack("#c", &changes, &isupport)?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pack_line_length() -> Result<(), Box<dyn Error>> {
+        let mut isupport = ISupport::new();
+        isupport.add_token("MODES");
+        let mask = "x".repeat(100);
+        let changes = vec![change(true, 'b', Some(&mask)); 10];
+        let lines = pack("#c", &changes, &isupport)?;
+        assert_eq!(3, lines.len());
+        for line in &lines {
+            assert!(line.to_string().len() <= 510);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pack_invalid() {
+        let isupport = ISupport::new();
+        let changes = vec![change(true, 'k', Some("a\r\nQUIT"))];
+        assert_eq!(
+            Err(BuildError::InvalidCharacter {
+                part: "param",
+                character: '\r'
+            }),
+            pack("#c", &changes, &isupport)
+        );
+        let changes = vec![change(true, 'k', Some("a b\0"))];
+        assert_eq!(
+            Err(BuildError::InvalidCharacter {
+                part: "trailing",
+                character: '\0'
+            }),
+            pack("#c", &changes, &isupport)
+        );
+        let changes = vec![change(true, 'm', None)];
+        assert_eq!(Err(BuildError::EmptyParam), pack("", &changes, &isupport));
+        assert_eq!(
+            Err(BuildError::InvalidCharacter {
+                part: "param",
+                character: ' '
+            }),
+            pack("#a #b", &changes, &isupport)
+        );
+    }
+}
@@ -1,7 +1,14 @@
+use crate::command::Command;
+use crate::ctcp::{Ctcp, Kind};
 use crate::errors::ParserError;
+use crate::formatting;
+use crate::formatting::Spans;
+use crate::numeric::Numeric;
 use crate::prefix::Prefix;
+use crate::tags;
+use crate::tags::Tags;
 use crate::tokenizer::Tokenizer;
-use std::collections::HashMap;
+use std::borrow::Cow;
 use std::convert::TryFrom;
 
 /// Fully parsed Message instead of parsing on demand. Instead of
@@ -13,7 +20,7 @@ use std::convert::TryFrom;
 /// depends on its source string.
 #[derive(Debug, Clone, Eq, PartialEq, Default)]
 pub struct Parsed<'a> {
-    tags: HashMap<&'a str, &'a str>,
+    tags: Tags<&'a str, &'a str>,
     prefix: Option<Prefix<'a>>,
     command: Option<&'a str>,
     params: Vec<Option<&'a str>>,
@@ -22,7 +29,7 @@ pub struct Parsed<'a> {
 
 impl<'a> Parsed<'a> {
     pub(crate) fn new(
-        tags: HashMap<&'a str, &'a str>,
+        tags: Tags<&'a str, &'a str>,
         prefix: Option<Prefix<'a>>,
         command: Option<&'a str>,
         params: Vec<Option<&'a str>>,
@@ -41,6 +48,16 @@ impl<'a> Parsed<'a> {
         self.command
     }
 
+    /// Returns the typed command if the command has been parsed.
+    pub fn typed_command(&self) -> Option<Command<'a>> {
+        self.command.map(Command::from)
+    }
+
+    /// Returns the numeric if the command is a three digit numeric reply.
+    pub fn numeric(&self) -> Option<Numeric> {
+        self.command.and_then(Numeric::parse)
+    }
+
     pub fn prefix(&self) -> Option<&Prefix<'a>> {
         self.prefix.as_ref()
     }
@@ -49,6 +66,7 @@ impl<'a> Parsed<'a> {
         self.params.iter()
     }
 
+    /// Returns the tags in the order of the message.
     pub fn tags(&self) -> impl Iterator<Item = (&&'a str, &&'a str)> {
         self.tags.iter()
     }
@@ -57,6 +75,25 @@ impl<'a> Parsed<'a> {
         self.tags.get(key).copied()
     }
 
+    /// Returns the unescaped value of a tag. Borrows from the message if the value
+    /// doesn't contain any escape sequences.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::Message;
+    /// # fn main() -> Result<(), irc_rust::errors::ParserError> {
+    /// let message = Message::from("@key=hello\\sworld\\: CMD");
+    /// let parsed = message.parse()?;
+    /// assert_eq!(Some("hello\\sworld\\:"), parsed.tag("key"));
+    /// assert_eq!(Some("hello world;"), parsed.tag_unescaped("key").as_deref());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tag_unescaped(&self, key: &str) -> Option<Cow<'a, str>> {
+        self.tag(key).map(tags::unescape)
+    }
+
     /// Returns a param with given original index. If the [Parsed] instance
     /// represents a partially parsed message the original index will preserve.
     ///
@@ -97,16 +134,42 @@ impl<'a> Parsed<'a> {
         self.trailing
     }
 
+    /// Returns the trailing parameter parsed into spans of mIRC formatted text.
+    ///
+    /// # Usage
+    ///
+    /// ```rust
+    /// use irc_rust::Message;
+    /// # fn main() -> Result<(), irc_rust::errors::ParserError> {
+    /// let message = Message::from("PRIVMSG #channel :Hello \x02World\x02!");
+    /// let parsed = message.parse()?;
+    /// let spans = parsed.formatted_trailing().unwrap().collect::<Vec<_>>();
+    /// assert_eq!("World", spans[1].text);
+    /// assert!(spans[1].style.bold);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn formatted_trailing(&self) -> Option<Spans<'a>> {
+        self.trailing.map(formatting::parse)
+    }
+
+    /// Returns the CTCP message if this is a `PRIVMSG` or `NOTICE` with a trailing parameter
+    /// starting with `\x01`.
+    pub fn ctcp(&self) -> Option<Ctcp<'a>> {
+        let kind = Kind::from_command(self.typed_command()?)?;
+        Ctcp::parse(kind, self.trailing?)
+    }
+
     pub fn prefix_name(&self) -> Option<&'a str> {
-        self.prefix.as_ref().map(|&(name, _user, _host)| name)
+        self.prefix.map(|prefix| prefix.name())
     }
 
     pub fn prefix_user(&self) -> Option<&'a str> {
-        self.prefix.as_ref().and_then(|&(_name, user, _host)| user)
+        self.prefix.and_then(|prefix| prefix.user())
     }
 
     pub fn prefix_host(&self) -> Option<&'a str> {
-        self.prefix.as_ref().and_then(|&(_name, _user, host)| host)
+        self.prefix.and_then(|prefix| prefix.host())
     }
 }
 
@@ -116,15 +179,13 @@ impl<'a> TryFrom<&'a str> for Parsed<'a> {
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
         let mut tokenizer = Tokenizer::new(value)?.tags();
         let iter = tokenizer.as_iter();
-        let mut tags = HashMap::new();
+        let mut tags = Tags::new();
         for res in iter {
             let (key, value) = res?;
             tags.insert(key, value);
         }
         let mut tokenizer = tokenizer.prefix();
-        let prefix = tokenizer
-            .parts()?
-            .map(|(name, user, host)| (name, user, host));
+        let prefix = tokenizer.parts()?;
         let mut tokenizer = tokenizer.command();
         let command = tokenizer.command()?;
         let mut tokenizer = tokenizer.params();
@@ -143,6 +204,7 @@ impl<'a> TryFrom<&'a str> for Parsed<'a> {
 
 #[cfg(test)]
 mod tests {
+    use crate::prefix::Prefix;
     use crate::Message;
     use std::error::Error;
 
@@ -160,8 +222,8 @@ mod tests {
         assert_eq!(Some("value1"), parsed.tag("tag1"));
         assert_eq!(Some("value2"), parsed.tag("tag2"));
         assert_eq!(
-            Some(("name", Some("user"), Some("host"))),
-            parsed.prefix().map(|prefix| (prefix.0, prefix.1, prefix.2))
+            Some(&Prefix::new("name", Some("user"), Some("host"))),
+            parsed.prefix()
         );
         assert_eq!(Some("param0"), parsed.param(0));
         assert_eq!(Some("Trailing Parameter!"), parsed.trailing());
@@ -1 +1,292 @@
-pub type Prefix<'a> = (&'a str, Option<&'a str>, Option<&'a str>);
+//! The prefix of a message identifying its origin.
+//!
+//! A prefix is either the name of a server like `irc.example.net` or a user in the form
+//! `nick[!user][@host]`. [Prefix] references the parts of a message while [OwnedPrefix] owns
+//! them.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::prefix::{OwnedPrefix, Prefix};
+//! use irc_rust::Message;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let message = Message::from(":nick!user@host PRIVMSG #channel :Hello");
+//! let prefix = message.prefix()?.unwrap();
+//! assert_eq!(Some("nick"), prefix.nick());
+//! assert_eq!(Some("user"), prefix.user());
+//! assert_eq!(Some("host"), prefix.host());
+//! assert_eq!("nick!user@host", prefix.to_string());
+//!
+//! let server: OwnedPrefix = "irc.example.net".parse()?;
+//! assert!(server.as_prefix().is_server());
+//! assert_eq!(None, server.as_prefix().nick());
+//!
+//! let message = Message::builder("NOTICE")
+//!     .with_prefix(server)
+//!     .param("*")
+//!     .trailing("Welcome")
+//!     .build();
+//! assert_eq!(":irc.example.net NOTICE * :Welcome", message.to_string());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::builder;
+use crate::errors::{Component, ParserError, ParserErrorKind};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// A prefix referencing the parts of a message.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Prefix<'a> {
+    name: &'a str,
+    user: Option<&'a str>,
+    host: Option<&'a str>,
+}
+
+impl<'a> Prefix<'a> {
+    pub fn new(name: &'a str, user: Option<&'a str>, host: Option<&'a str>) -> Self {
+        Prefix { name, user, host }
+    }
+
+    /// Returns the server name or nickname.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the nickname or `None` for server prefixes.
+    pub fn nick(&self) -> Option<&'a str> {
+        if self.is_server() {
+            None
+        } else {
+            Some(self.name)
+        }
+    }
+
+    pub fn user(&self) -> Option<&'a str> {
+        self.user
+    }
+
+    pub fn host(&self) -> Option<&'a str> {
+        self.host
+    }
+
+    /// Returns true if the prefix is the name of a server.
+    ///
+    /// Server names contain a `.` which isn't allowed in nicknames and have no user or host.
+    pub fn is_server(&self) -> bool {
+        self.user.is_none() && self.host.is_none() && self.name.contains('.')
+    }
+
+    /// Copies the parts into an [OwnedPrefix].
+    pub fn into_owned(self) -> OwnedPrefix {
+        OwnedPrefix::from(self)
+    }
+}
+
+impl<'a> fmt::Display for Prefix<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)?;
+        if let Some(user) = self.user {
+            write!(f, "!{}", user)?;
+        }
+        if let Some(host) = self.host {
+            write!(f, "@{}", host)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Prefix<'a> {
+    type Error = ParserError;
+
+    /// Parses a prefix with or without the leading `:`.
+    ///
+    /// The parts are validated like in [Builder::try_with_prefix], so a user requires a host.
+    ///
+    /// [Builder::try_with_prefix]: crate::builder::Builder::try_with_prefix
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        let start = if value.starts_with(':') { 1 } else { 0 };
+        let prefix = &value[start..];
+        if let Some(space) = prefix.find(' ') {
+            return Err(ParserError::new(
+                ParserErrorKind::InvalidPrefixName,
+                Component::Prefix,
+                start + space,
+                1,
+            ));
+        }
+        let (rest, host) = match prefix.find('@') {
+            Some(index) => (&prefix[..index], Some(&prefix[index + 1..])),
+            None => (prefix, None),
+        };
+        let (name, user) = match rest.find('!') {
+            Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+            None => (rest, None),
+        };
+        if name.is_empty() {
+            return Err(ParserError::new(
+                ParserErrorKind::PrefixWithoutName,
+                Component::Prefix,
+                0,
+                value.len(),
+            ));
+        }
+        let prefix = Prefix::new(name, user, host);
+        builder::validate_prefix(&prefix)
+            .map_err(|why| builder::prefix_error(value, &prefix, why))?;
+        Ok(prefix)
+    }
+}
+
+/// A prefix owning its parts.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct OwnedPrefix {
+    name: String,
+    user: Option<String>,
+    host: Option<String>,
+}
+
+impl OwnedPrefix {
+    pub fn new<SN, SU, SH>(name: SN, user: Option<SU>, host: Option<SH>) -> Self
+    where
+        SN: ToString,
+        SU: ToString,
+        SH: ToString,
+    {
+        OwnedPrefix {
+            name: name.to_string(),
+            user: user.map(|user| user.to_string()),
+            host: host.map(|host| host.to_string()),
+        }
+    }
+
+    /// Returns a [Prefix] referencing the parts of this prefix.
+    pub fn as_prefix(&self) -> Prefix<'_> {
+        Prefix::new(&self.name, self.user.as_deref(), self.host.as_deref())
+    }
+}
+
+impl<'a> From<Prefix<'a>> for OwnedPrefix {
+    fn from(prefix: Prefix<'a>) -> Self {
+        OwnedPrefix {
+            name: prefix.name.to_string(),
+            user: prefix.user.map(|user| user.to_string()),
+            host: prefix.host.map(|host| host.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for OwnedPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_prefix().fmt(f)
+    }
+}
+
+impl FromStr for OwnedPrefix {
+    type Err = ParserError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Prefix::try_from(s).map(OwnedPrefix::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::{Component, ParserError, ParserErrorKind};
+    use crate::prefix::{OwnedPrefix, Prefix};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Ok(Prefix::new("nick", Some("user"), Some("host"))),
+            Prefix::try_from(":nick!user@host")
+        );
+        assert_eq!(
+            Ok(Prefix::new("nick", None, Some("host"))),
+            Prefix::try_from("nick@host")
+        );
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::InvalidPrefixUser,
+                Component::Prefix,
+                6,
+                4
+            )),
+            Prefix::try_from(":nick!user")
+        );
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::InvalidPrefixHost,
+                Component::Prefix,
+                10,
+                0
+            )),
+            Prefix::try_from("nick!user@")
+        );
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::InvalidCharacter,
+                Component::Prefix,
+                2,
+                1
+            )),
+            Prefix::try_from("ni\0ck")
+        );
+        assert_eq!(
+            Ok(Prefix::new("irc.example.net", None, None)),
+            Prefix::try_from("irc.example.net")
+        );
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::PrefixWithoutName,
+                Component::Prefix,
+                0,
+                6
+            )),
+            Prefix::try_from(":!user")
+        );
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::InvalidPrefixName,
+                Component::Prefix,
+                5,
+                1
+            )),
+            Prefix::try_from(":nick CMD")
+        );
+    }
+
+    #[test]
+    fn test_server() {
+        let server = Prefix::new("irc.example.net", None, None);
+        assert!(server.is_server());
+        assert_eq!(None, server.nick());
+        assert_eq!("irc.example.net", server.name());
+
+        let nick = Prefix::new("nick", None, None);
+        assert!(!nick.is_server());
+        assert_eq!(Some("nick"), nick.nick());
+
+        let user = Prefix::new("nick", None, Some("host.example.net"));
+        assert!(!user.is_server());
+    }
+
+    #[test]
+    fn test_display() {
+        for prefix in ["nick!user@host", "nick@host", "nick", "irc.example.net"].iter() {
+            assert_eq!(*prefix, Prefix::try_from(*prefix).unwrap().to_string());
+            assert_eq!(*prefix, prefix.parse::<OwnedPrefix>().unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn test_owned() {
+        let prefix = Prefix::new("nick", Some("user"), Some("host"));
+        let owned = prefix.into_owned();
+        assert_eq!(prefix, owned.as_prefix());
+        assert_eq!(OwnedPrefix::new("nick", Some("user"), Some("host")), owned);
+    }
+}
@@ -0,0 +1,660 @@
+//! Tokenizer and message working on bytes instead of `str`.
+//!
+//! IRC doesn't mandate an encoding and some networks still carry Latin-1 or CP1252 text.
+//! [RawTokenizer] and [RawMessage] accept such lines without converting them. All parts
+//! are returned as byte slices exactly as they occur in the line and are only decoded if a
+//! `&str` view is requested.
+
+use crate::charset;
+use crate::charset::Charset;
+use crate::errors::{Component, ParserError, ParserErrorKind};
+use crate::message::Message;
+use crate::tokenizer::{
+    CommandState, ParamsState, PrefixState, Start, State, TagsState, TrailingState,
+};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::str::Utf8Error;
+use std::string::FromUtf8Error;
+
+pub type RawPrefix<'a> = (&'a [u8], Option<&'a [u8]>, Option<&'a [u8]>);
+
+/// Returns the position of the first byte contained in `needles`.
+fn find_any(haystack: &[u8], needles: &[u8]) -> Option<usize> {
+    haystack.iter().position(|byte| needles.contains(byte))
+}
+
+/// Returns the position of the first occurrence of `needle`.
+fn find_seq(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Byte based counterpart of [Tokenizer](crate::tokenizer::Tokenizer) using the same
+/// states and transitions.
+///
+/// # Usage
+///
+/// ```rust
+/// use irc_rust::raw::RawTokenizer;
+/// # fn main() -> Result<(), irc_rust::errors::ParserError> {
+/// // "Grüße" encoded as Latin-1
+/// let mut tokenizer = RawTokenizer::new(b"PRIVMSG #channel :Gr\xfc\xdfe")?.command();
+/// assert_eq!(&b"PRIVMSG"[..], tokenizer.command()?);
+/// let mut tokenizer = tokenizer.params();
+/// assert_eq!(Some(&b"#channel"[..]), tokenizer.as_iter().next());
+/// assert_eq!(Some(&b"Gr\xfc\xdfe"[..]), tokenizer.trailing().trailing());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub struct RawTokenizer<'a, T: State> {
+    raw: &'a [u8],
+    /// The whole message to compute offsets of errors.
+    origin: &'a [u8],
+    state: PhantomData<T>,
+}
+
+impl<'a, S: State> RawTokenizer<'a, S> {
+    /// Returns the offset of the remaining message in the whole message.
+    fn offset(&self) -> usize {
+        self.origin.len() - self.raw.len()
+    }
+
+    fn error(
+        &self,
+        kind: ParserErrorKind,
+        component: Component,
+        start: usize,
+        length: usize,
+    ) -> ParserError {
+        ParserError::new(kind, component, self.offset() + start, length)
+    }
+
+    fn no_command(&self) -> ParserError {
+        let end = self.raw.len();
+        self.error(ParserErrorKind::NoCommand, Component::Command, end, 0)
+    }
+
+    fn transition<T: State>(self) -> RawTokenizer<'a, T> {
+        RawTokenizer {
+            raw: self.raw,
+            origin: self.origin,
+            state: PhantomData,
+        }
+    }
+
+    fn skip_until_byte(&mut self, byte: u8, skip_byte: bool) {
+        if self.raw.starts_with(&[byte]) {
+            return;
+        }
+
+        let end = find_any(self.raw, &[byte])
+            .map(|pos| if skip_byte { pos + 1 } else { pos })
+            .unwrap_or(self.raw.len());
+        self.raw = &self.raw[end..];
+    }
+
+    fn skip_tags(&mut self) {
+        // include ';' to also skip if tags have been partially parsed
+        if self.raw.starts_with(b"@") || self.raw.starts_with(b";") {
+            self.skip_until_byte(b' ', true);
+        }
+    }
+
+    fn skip_prefix(&mut self) {
+        self.skip_tags();
+        if matches!(self.raw.first(), Some(b':') | Some(b'!') | Some(b'@')) {
+            self.skip_until_byte(b' ', true);
+        }
+    }
+
+    fn skip_command(&mut self) {
+        self.skip_prefix();
+        self.skip_until_byte(b' ', false);
+    }
+
+    fn skip_params(&mut self) {
+        self.skip_command();
+        if !self.raw.starts_with(b" :") {
+            let end = find_seq(self.raw, b" :").unwrap_or(self.raw.len());
+            self.raw = &self.raw[end..];
+        }
+    }
+}
+
+impl<'a> RawTokenizer<'a, Start> {
+    pub fn new(raw: &'a [u8]) -> Result<Self, ParserError> {
+        if raw.is_empty() {
+            Err(ParserError::new(
+                ParserErrorKind::NoCommand,
+                Component::Command,
+                0,
+                0,
+            ))
+        } else {
+            Ok(RawTokenizer {
+                raw,
+                origin: raw,
+                state: PhantomData,
+            })
+        }
+    }
+
+    pub fn tags(self) -> RawTokenizer<'a, TagsState> {
+        self.transition()
+    }
+
+    pub fn prefix(mut self) -> RawTokenizer<'a, PrefixState> {
+        self.skip_tags();
+        self.transition()
+    }
+
+    pub fn command(mut self) -> RawTokenizer<'a, CommandState> {
+        self.skip_prefix();
+        self.transition()
+    }
+
+    pub fn params(mut self) -> RawTokenizer<'a, ParamsState> {
+        self.skip_command();
+        self.transition()
+    }
+
+    pub fn trailing(mut self) -> RawTokenizer<'a, TrailingState> {
+        self.skip_params();
+        self.transition()
+    }
+}
+
+impl<'a> RawTokenizer<'a, TagsState> {
+    pub fn as_iter(&mut self) -> IntoRawTagsIter<'a> {
+        IntoRawTagsIter(*self)
+    }
+
+    pub fn prefix(mut self) -> RawTokenizer<'a, PrefixState> {
+        self.skip_tags();
+        self.transition()
+    }
+
+    pub fn command(mut self) -> RawTokenizer<'a, CommandState> {
+        self.skip_prefix();
+        self.transition()
+    }
+
+    pub fn params(mut self) -> RawTokenizer<'a, ParamsState> {
+        self.skip_command();
+        self.transition()
+    }
+
+    pub fn trailing(mut self) -> RawTokenizer<'a, TrailingState> {
+        self.skip_params();
+        self.transition()
+    }
+}
+
+impl<'a> IntoIterator for RawTokenizer<'a, TagsState> {
+    type Item = Result<(&'a [u8], &'a [u8]), ParserError>;
+    type IntoIter = IntoRawTagsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoRawTagsIter(self)
+    }
+}
+
+pub struct IntoRawTagsIter<'a>(RawTokenizer<'a, TagsState>);
+
+impl<'a> Iterator for IntoRawTagsIter<'a> {
+    type Item = Result<(&'a [u8], &'a [u8]), ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !(self.0.raw.starts_with(b"@") || self.0.raw.starts_with(b";")) {
+            return None;
+        }
+        let key_start = 1;
+        let key_end = match find_any(&self.0.raw[key_start..], b"=; ") {
+            Some(key_end) => key_start + key_end,
+            None => {
+                let why = self.0.error(
+                    ParserErrorKind::NoTagKeyEnd,
+                    Component::Tags,
+                    key_start,
+                    self.0.raw.len() - key_start,
+                );
+                // Skip till the end as only tags seem to be present
+                self.0.raw = &self.0.raw[self.0.raw.len()..];
+                return Some(Err(why));
+            }
+        };
+        let val_start = if self.0.raw[key_end] == b'=' {
+            key_end + 1
+        } else {
+            key_end
+        };
+        let val_end = match find_any(&self.0.raw[val_start..], b"; ") {
+            Some(val_end) => val_start + val_end,
+            None => {
+                let why = self.0.error(
+                    ParserErrorKind::NoTagValueEnd,
+                    Component::Tags,
+                    val_start,
+                    self.0.raw.len() - val_start,
+                );
+                // Skip till the end as only tags seem to be present
+                self.0.raw = &self.0.raw[self.0.raw.len()..];
+                return Some(Err(why));
+            }
+        };
+        let key_val = (
+            &self.0.raw[key_start..key_end],
+            &self.0.raw[val_start..val_end],
+        );
+        self.0.raw = &self.0.raw[val_end..];
+        Some(Ok(key_val))
+    }
+}
+
+impl<'a> RawTokenizer<'a, PrefixState> {
+    /// Returns the part of the prefix starting with `start` and ending before any of `ends`.
+    fn prefix_part(&mut self, start: u8, ends: &[u8]) -> Result<Option<&'a [u8]>, ParserError> {
+        if !self.raw.starts_with(&[start]) {
+            return Ok(None);
+        }
+        let end = find_any(self.raw, ends).ok_or_else(|| self.no_command())?;
+        let (part, rest) = self.raw.split_at(end);
+        self.raw = rest;
+        Ok(Some(&part[1..]))
+    }
+
+    pub fn name(&mut self) -> Result<Option<&'a [u8]>, ParserError> {
+        if self.raw.starts_with(b" ") {
+            self.raw = &self.raw[1..];
+        }
+        self.prefix_part(b':', b"!@ ")
+    }
+
+    pub fn user(&mut self) -> Result<Option<&'a [u8]>, ParserError> {
+        self.prefix_part(b'!', b"@ ")
+    }
+
+    pub fn host(&mut self) -> Result<Option<&'a [u8]>, ParserError> {
+        self.prefix_part(b'@', b" ")
+    }
+
+    /// Returns [None] if no prefix is present.
+    pub fn parts(&mut self) -> Result<Option<RawPrefix<'a>>, ParserError> {
+        if self.raw.starts_with(b" ") {
+            self.raw = &self.raw[1..];
+        }
+        if !self.raw.starts_with(b":") {
+            return Ok(None);
+        }
+        let (name, user, host) = (self.name()?, self.user()?, self.host()?);
+        Ok(name.map(|name| (name, user, host)))
+    }
+
+    pub fn command(mut self) -> RawTokenizer<'a, CommandState> {
+        self.skip_prefix();
+        self.transition()
+    }
+
+    pub fn params(mut self) -> RawTokenizer<'a, ParamsState> {
+        self.skip_command();
+        self.transition()
+    }
+
+    pub fn trailing(mut self) -> RawTokenizer<'a, TrailingState> {
+        self.skip_params();
+        self.transition()
+    }
+}
+
+impl<'a> RawTokenizer<'a, CommandState> {
+    pub fn command(&mut self) -> Result<&'a [u8], ParserError> {
+        if self.raw.starts_with(b" ") {
+            self.raw = &self.raw[1..];
+        }
+
+        let end = find_any(self.raw, b" ").unwrap_or(self.raw.len());
+        let (command, rest) = self.raw.split_at(end);
+        if command.is_empty() {
+            return Err(self.error(ParserErrorKind::NoCommand, Component::Command, 0, 0));
+        }
+        self.raw = rest;
+        Ok(command)
+    }
+
+    pub fn params(mut self) -> RawTokenizer<'a, ParamsState> {
+        self.skip_command();
+        self.transition()
+    }
+
+    pub fn trailing(mut self) -> RawTokenizer<'a, TrailingState> {
+        self.skip_params();
+        self.transition()
+    }
+}
+
+impl<'a> RawTokenizer<'a, ParamsState> {
+    pub fn trailing(mut self) -> RawTokenizer<'a, TrailingState> {
+        self.skip_params();
+        self.transition()
+    }
+
+    pub fn as_iter(&mut self) -> IntoRawParamsIter<'a> {
+        IntoRawParamsIter(*self)
+    }
+}
+
+impl<'a> IntoIterator for RawTokenizer<'a, ParamsState> {
+    type Item = &'a [u8];
+    type IntoIter = IntoRawParamsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoRawParamsIter(self)
+    }
+}
+
+pub struct IntoRawParamsIter<'a>(RawTokenizer<'a, ParamsState>);
+
+impl<'a> Iterator for IntoRawParamsIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.0.raw.starts_with(b" ") || self.0.raw.starts_with(b" :") {
+            return None;
+        }
+        self.0.raw = &self.0.raw[1..];
+        let end = find_any(self.0.raw, b" ").unwrap_or(self.0.raw.len());
+        let (param, rest) = self.0.raw.split_at(end);
+        self.0.raw = rest;
+        Some(param)
+    }
+}
+
+impl<'a> RawTokenizer<'a, TrailingState> {
+    pub fn trailing(&self) -> Option<&'a [u8]> {
+        if self.raw.starts_with(b" :") {
+            Some(&self.raw[2..])
+        } else {
+            None
+        }
+    }
+}
+
+/// Byte based counterpart of [Message] for lines which aren't necessarily valid UTF-8.
+///
+/// Parses its parts lazily on method invocations and returns them as byte slices. Use
+/// [RawMessage::to_str] or convert it into a [Message] to get `&str` views.
+///
+/// # Usage
+///
+/// ```rust
+/// use irc_rust::raw::RawMessage;
+/// use irc_rust::Message;
+/// use std::convert::TryFrom;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let message = RawMessage::from(&b":nick!user@host PRIVMSG #channel :caf\xe9"[..]);
+/// assert_eq!(&b"PRIVMSG"[..], message.command()?);
+/// assert_eq!(Some(&b"caf\xe9"[..]), message.trailing()?);
+/// assert!(message.to_str().is_err());
+///
+/// let message = RawMessage::from("PING :server");
+/// assert_eq!(Message::from("PING :server"), Message::try_from(message)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Eq, Ord, PartialOrd, PartialEq, Hash)]
+pub struct RawMessage {
+    raw: Vec<u8>,
+}
+
+impl RawMessage {
+    pub fn tokenizer(&self) -> Result<RawTokenizer<'_, Start>, ParserError> {
+        RawTokenizer::new(self.raw.as_slice())
+    }
+
+    /// Returns tags without unescaping the values.
+    pub fn tags(&self) -> Result<IntoRawTagsIter<'_>, ParserError> {
+        self.tokenizer()
+            .map(|tokenizer| tokenizer.tags().into_iter())
+    }
+
+    /// Returns the Prefix if present.
+    pub fn prefix(&self) -> Result<Option<RawPrefix<'_>>, ParserError> {
+        self.tokenizer()
+            .and_then(|tokenizer| tokenizer.prefix().parts())
+    }
+
+    /// Returns the command the message represents.
+    pub fn command(&self) -> Result<&[u8], ParserError> {
+        self.tokenizer()
+            .and_then(|tokenizer| tokenizer.command().command())
+    }
+
+    /// Returns the params if any are present.
+    pub fn params(&self) -> Result<impl Iterator<Item = &[u8]>, ParserError> {
+        self.tokenizer()
+            .map(|tokenizer| tokenizer.params().into_iter())
+    }
+
+    /// Returns the trailing parameter if any is present.
+    pub fn trailing(&self) -> Result<Option<&[u8]>, ParserError> {
+        self.tokenizer()
+            .map(|tokenizer| tokenizer.trailing().trailing())
+    }
+
+    /// Returns the params decoded as UTF-8 or in the **fallback** charset if they aren't
+    /// valid UTF-8.
+    pub fn params_decoded(
+        &self,
+        fallback: Charset,
+    ) -> Result<impl Iterator<Item = Cow<'_, str>>, ParserError> {
+        self.params()
+            .map(move |params| params.map(move |param| charset::decode(param, fallback)))
+    }
+
+    /// Returns the trailing parameter decoded as UTF-8 or in the **fallback** charset if it
+    /// isn't valid UTF-8.
+    pub fn trailing_decoded(&self, fallback: Charset) -> Result<Option<Cow<'_, str>>, ParserError> {
+        self.trailing()
+            .map(|trailing| trailing.map(|trailing| charset::decode(trailing, fallback)))
+    }
+
+    /// Decodes the message as UTF-8 or in the **fallback** charset if it isn't valid UTF-8.
+    /// Words are decoded separately to support lines mixing both. Tags are always decoded as
+    /// UTF-8.
+    pub fn decode(&self, fallback: Charset) -> Message {
+        Message::from(charset::decode_line(&self.raw, fallback))
+    }
+
+    /// Returns the raw bytes of the message.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.raw.as_slice()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.raw
+    }
+
+    /// Returns the message as `&str` if it is valid UTF-8.
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(&self.raw)
+    }
+}
+
+impl From<Vec<u8>> for RawMessage {
+    fn from(raw: Vec<u8>) -> Self {
+        RawMessage { raw }
+    }
+}
+
+impl From<&[u8]> for RawMessage {
+    fn from(raw: &[u8]) -> Self {
+        RawMessage { raw: raw.to_vec() }
+    }
+}
+
+impl From<&str> for RawMessage {
+    fn from(raw: &str) -> Self {
+        RawMessage {
+            raw: raw.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl From<Message> for RawMessage {
+    fn from(message: Message) -> Self {
+        RawMessage {
+            raw: message.to_string().into_bytes(),
+        }
+    }
+}
+
+impl TryFrom<RawMessage> for Message {
+    type Error = FromUtf8Error;
+
+    /// Converts the message if it is valid UTF-8. The error contains the original bytes.
+    fn try_from(message: RawMessage) -> Result<Self, Self::Error> {
+        String::from_utf8(message.raw).map(Message::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::charset::Charset;
+    use crate::errors::{Component, ParserError, ParserErrorKind};
+    use crate::raw::{RawMessage, RawTokenizer};
+    use crate::tokenizer::Tokenizer;
+    use crate::Message;
+    use std::borrow::Cow;
+    use std::convert::TryFrom;
+    use std::error::Error;
+
+    #[test]
+    fn test_all() -> Result<(), Box<dyn Error>> {
+        let mut tokenizer = RawTokenizer::new(
+            b"@key1=value1;key2=\xe4 :n\xe4me!user@host CMD param0 p\xe4ram1 :Tr\xe4iling :)",
+        )?
+        .tags();
+        let mut iter = tokenizer.as_iter();
+        assert_eq!(Some(Ok((&b"key1"[..], &b"value1"[..]))), iter.next());
+        assert_eq!(Some(Ok((&b"key2"[..], &b"\xe4"[..]))), iter.next());
+        assert_eq!(None, iter.next());
+        let mut tokenizer = tokenizer.prefix();
+        assert_eq!(
+            Some((&b"n\xe4me"[..], Some(&b"user"[..]), Some(&b"host"[..]))),
+            tokenizer.parts()?
+        );
+        let mut tokenizer = tokenizer.command();
+        assert_eq!(&b"CMD"[..], tokenizer.command()?);
+        let mut tokenizer = tokenizer.params();
+        assert_eq!(
+            vec![&b"param0"[..], &b"p\xe4ram1"[..]],
+            tokenizer.as_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(&b"Tr\xe4iling :)"[..]),
+            tokenizer.trailing().trailing()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_as_tokenizer() -> Result<(), Box<dyn Error>> {
+        for line in &[
+            "CMD",
+            ":name CMD :",
+            ":name@host CMD param",
+            "@key1=;key2 CMD param0 param1 :trailing",
+        ] {
+            let message = Message::from(*line);
+            let raw = RawMessage::from(*line);
+            assert_eq!(message.command()?.as_bytes(), raw.command()?, "{}", line);
+            assert_eq!(
+                message
+                    .params()?
+                    .map(|param| param.as_bytes())
+                    .collect::<Vec<_>>(),
+                raw.params()?.collect::<Vec<_>>()
+            );
+            assert_eq!(
+                message.trailing()?.map(|trailing| trailing.as_bytes()),
+                raw.trailing()?
+            );
+            let tokenizer = Tokenizer::new(line)?.tags();
+            let expected = tokenizer
+                .into_iter()
+                .map(|tag| tag.map(|(key, value)| (key.as_bytes(), value.as_bytes())))
+                .collect::<Vec<_>>();
+            assert_eq!(expected, raw.tags()?.collect::<Vec<_>>());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::NoCommand,
+                Component::Command,
+                0,
+                0
+            )),
+            RawTokenizer::new(b"")
+        );
+        let message = RawMessage::from("@key=value");
+        assert_eq!(
+            Some(Err(ParserError::new(
+                ParserErrorKind::NoTagValueEnd,
+                Component::Tags,
+                5,
+                5
+            ))),
+            message.tags().unwrap().next()
+        );
+        let message = RawMessage::from(":name!user");
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::NoCommand,
+                Component::Command,
+                10,
+                0
+            )),
+            message.prefix()
+        );
+    }
+
+    #[test]
+    fn test_conversion() {
+        let message = RawMessage::from(&b"CMD :\xff"[..]);
+        let why = Message::try_from(message).unwrap_err();
+        assert_eq!(&b"CMD :\xff"[..], why.as_bytes());
+        let message = Message::from("CMD :trailing");
+        assert_eq!(
+            Ok(message.clone()),
+            Message::try_from(RawMessage::from(message))
+        );
+    }
+
+    #[test]
+    fn test_decoded() -> Result<(), Box<dyn Error>> {
+        let message = RawMessage::from(&b"@k=\xc3\xa4 CMD \xe4 \xc3\xa4 :\xe4"[..]);
+        assert_eq!(
+            vec!["ä", "ä"],
+            message.params_decoded(Charset::Latin1)?.collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(Cow::Borrowed("ä")),
+            RawMessage::from("CMD :ä").trailing_decoded(Charset::Latin1)?
+        );
+        assert_eq!(Some("ä".into()), message.trailing_decoded(Charset::Latin1)?);
+        assert_eq!(
+            Message::from("@k=ä CMD ä ä :ä"),
+            message.decode(Charset::Latin1)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,382 @@
+//! Escaping and unescaping of tag values as described in the
+//! [IRCv3 message-tags specification](https://ircv3.net/specs/extensions/message-tags#escaping-values).
+//!
+//! Tag values can't contain `;`, ` `, `\`, CR or LF in their raw form. These characters are
+//! escaped as `\:`, `\s`, `\\`, `\r` and `\n`.
+//!
+//! [Tags] keeps the tags of a message in their original order.
+
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+
+/// Escapes a tag value so it can be written into the tags section of a message.
+///
+/// Borrows the value if nothing has to be escaped.
+///
+/// # Usage
+///
+/// ```rust
+/// use irc_rust::tags::escape;
+///
+/// assert_eq!("plain", escape("plain"));
+/// assert_eq!("semi\\:colon\\sand\\sspace", escape("semi;colon and space"));
+/// ```
+pub fn escape(value: &str) -> Cow<'_, str> {
+    if !value.contains(&[';', ' ', '\\', '\r', '\n'][..]) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut escaped = String::with_capacity(value.len() + 4);
+    for ch in value.chars() {
+        match ch {
+            ';' => escaped.push_str("\\:"),
+            ' ' => escaped.push_str("\\s"),
+            '\\' => escaped.push_str("\\\\"),
+            '\r' => escaped.push_str("\\r"),
+            '\n' => escaped.push_str("\\n"),
+            ch => escaped.push(ch),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Unescapes a raw tag value as read from a message.
+///
+/// Borrows the value if it doesn't contain any escape sequences. As defined by the
+/// specification a `\` followed by an unknown character is dropped and a trailing
+/// `\` is removed.
+///
+/// # Usage
+///
+/// ```rust
+/// use irc_rust::tags::unescape;
+///
+/// assert_eq!("plain", unescape("plain"));
+/// assert_eq!("semi;colon and space", unescape("semi\\:colon\\sand\\sspace"));
+/// assert_eq!("b", unescape("\\b\\"));
+/// ```
+pub fn unescape(value: &str) -> Cow<'_, str> {
+    if !value.contains('\\') {
+        return Cow::Borrowed(value);
+    }
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some(':') => unescaped.push(';'),
+            Some('s') => unescaped.push(' '),
+            Some('r') => unescaped.push('\r'),
+            Some('n') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => break,
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// Number of tags up to which keys are looked up linearly.
+const INDEX_THRESHOLD: usize = 16;
+
+/// An ordered map of tags used by [Parsed](crate::parsed::Parsed) and
+/// [Builder](crate::builder::Builder).
+///
+/// Tags are kept in the order they were inserted. Inserting an existing key replaces its value
+/// but keeps its position, so for duplicate keys the last value wins as defined by the
+/// specification.
+///
+/// As messages usually only carry a few tags, up to 16 tags are stored in a `Vec` and looked
+/// up linearly. Past that an index of the keys is kept, so looking up and inserting a tag
+/// takes constant time on average and collecting the up to 4096 tags of a message linear
+/// time. Removing a tag takes linear time.
+///
+/// # Usage
+///
+/// ```rust
+/// use irc_rust::tags::Tags;
+///
+/// let mut tags = Tags::new();
+/// tags.insert("b", "1");
+/// tags.insert("a", "2");
+/// tags.insert("b", "3");
+/// assert_eq!(Some(&"3"), tags.get("b"));
+/// assert_eq!(vec![(&"b", &"3"), (&"a", &"2")], tags.iter().collect::<Vec<_>>());
+/// ```
+#[derive(Clone)]
+pub struct Tags<K, V> {
+    entries: Vec<(K, V)>,
+    /// Positions of the keys in the entries. Empty up to [INDEX_THRESHOLD] tags.
+    index: HashMap<K, usize>,
+}
+
+impl<K, V> Tags<K, V> {
+    pub fn new() -> Self {
+        Tags::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Tags {
+            entries: Vec::with_capacity(capacity),
+            index: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the tags in their order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Tags<K, V> {
+    /// Sorts the tags by their key.
+    pub fn sort(&mut self)
+    where
+        K: Ord,
+    {
+        self.entries
+            .sort_by(|(left, _), (right, _)| left.cmp(right));
+        self.reindex();
+    }
+
+    fn position<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.index.is_empty() {
+            self.entries
+                .iter()
+                .position(|(entry, _)| entry.borrow() == key)
+        } else {
+            self.index.get(key).copied()
+        }
+    }
+
+    fn reindex(&mut self) {
+        self.index.clear();
+        if self.entries.len() > INDEX_THRESHOLD {
+            self.index.extend(
+                self.entries
+                    .iter()
+                    .enumerate()
+                    .map(|(position, (key, _))| (key.clone(), position)),
+            );
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.position(key).map(|index| &self.entries[index].1)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.position(key).is_some()
+    }
+
+    /// Inserts a tag. If the key is already present its value is replaced in place and the
+    /// old value returned.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(index) = self.position(&key) {
+            return Some(std::mem::replace(&mut self.entries[index].1, value));
+        }
+        if self.index.is_empty() {
+            self.entries.push((key, value));
+            if self.entries.len() > INDEX_THRESHOLD {
+                self.reindex();
+            }
+        } else {
+            self.index.insert(key.clone(), self.entries.len());
+            self.entries.push((key, value));
+        }
+        None
+    }
+
+    /// Removes a tag keeping the order of the others.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.position(key)?;
+        let (_, value) = self.entries.remove(index);
+        if !self.index.is_empty() {
+            self.reindex();
+        }
+        Some(value)
+    }
+}
+
+impl<K, V> Default for Tags<K, V> {
+    fn default() -> Self {
+        Tags::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Tags<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tags")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+// The index is derived from the entries, so only they are compared and hashed.
+impl<K: PartialEq, V: PartialEq> PartialEq for Tags<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for Tags<K, V> {}
+
+impl<K: Hash, V: Hash> Hash for Tags<K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.entries.hash(state);
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Extend<(K, V)> for Tags<K, V> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> FromIterator<(K, V)> for Tags<K, V> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut tags = Tags::new();
+        tags.extend(iter);
+        tags
+    }
+}
+
+impl<K, V> IntoIterator for Tags<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tags::{escape, unescape, Tags};
+    use std::borrow::Cow;
+
+    fn is_borrowed(value: Cow<str>) -> bool {
+        match value {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        }
+    }
+
+    #[test]
+    fn test_borrowed() {
+        assert!(is_borrowed(escape("value")));
+        assert!(is_borrowed(unescape("value")));
+        assert!(is_borrowed(unescape("")));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!("\\:\\s\\\\\\r\\n", escape("; \\\r\n"));
+        assert_eq!("a\\sb\\:c", escape("a b;c"));
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!("; \\\r\n", unescape("\\:\\s\\\\\\r\\n"));
+        assert_eq!("ab", unescape("\\ab"));
+        assert_eq!("a", unescape("a\\"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for value in &["", "plain", "a b;c", "\\s", "line\r\nbreak", "ünï cödé;"] {
+            assert_eq!(*value, unescape(&escape(value)));
+        }
+    }
+
+    #[test]
+    fn test_tags_order() {
+        let tags = vec![("z", "1"), ("a", "2"), ("m", "3"), ("a", "4")]
+            .into_iter()
+            .collect::<Tags<_, _>>();
+        assert_eq!(3, tags.len());
+        assert_eq!(vec![&"z", &"a", &"m"], tags.keys().collect::<Vec<_>>());
+        assert_eq!(Some(&"4"), tags.get("a"));
+        assert!(!tags.contains_key("b"));
+    }
+
+    #[test]
+    fn test_tags_modify() {
+        let mut tags = Tags::new();
+        assert_eq!(None, tags.insert("c".to_string(), 1));
+        assert_eq!(None, tags.insert("a".to_string(), 2));
+        assert_eq!(None, tags.insert("b".to_string(), 3));
+        assert_eq!(Some(1), tags.insert("c".to_string(), 4));
+        assert_eq!(Some(2), tags.remove("a"));
+        assert_eq!(None, tags.remove("a"));
+        assert_eq!(
+            vec![("c".to_string(), 4), ("b".to_string(), 3)],
+            tags.clone().into_iter().collect::<Vec<_>>()
+        );
+        tags.sort();
+        assert_eq!(vec!["b", "c"], tags.keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_tags_index() {
+        let keys = (0..100).map(|key| key.to_string()).collect::<Vec<_>>();
+        let mut tags = keys
+            .iter()
+            .map(|key| (key.as_str(), key.len()))
+            .collect::<Tags<_, _>>();
+        assert_eq!(100, tags.len());
+        assert_eq!(Some(&2), tags.get("42"));
+        assert_eq!(Some(2), tags.insert("42", 0));
+        assert_eq!(Some(&0), tags.get("42"));
+        assert_eq!(Some(0), tags.remove("42"));
+        assert_eq!(Some(&2), tags.get("43"));
+        assert_eq!(None, tags.insert("100", 3));
+        assert_eq!(Some(&3), tags.get("100"));
+        for key in &keys[..90] {
+            tags.remove(key.as_str());
+        }
+        assert_eq!(
+            vec![&"90", &"91", &"92", &"93", &"94", &"95", &"96", &"97", &"98", &"99", &"100"],
+            tags.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&2), tags.get("99"));
+        tags.sort();
+        assert_eq!(Some(&"100"), tags.keys().next());
+    }
+}
@@ -1,5 +1,7 @@
+use crate::command::Command;
 use crate::errors::ParserError;
 use crate::message::Message;
+use crate::numeric::Numeric;
 use std::error::Error;
 
 #[test]
@@ -27,14 +29,14 @@ fn test_parse() -> Result<(), Box<dyn Error>> {
             || (*key2 == "key1" && *val2 == "value1" && *key == "key2" && *val == "value2")
     );
 
-    let (name, user, host) = message.prefix()?.unwrap();
-    assert_eq!(name, "name");
-    assert_eq!(user, Some("user"));
-    assert_eq!(host, Some("host"));
+    let prefix = message.prefix()?.unwrap();
+    assert_eq!(prefix.name(), "name");
+    assert_eq!(prefix.user(), Some("user"));
+    assert_eq!(prefix.host(), Some("host"));
 
     assert_eq!(message.command()?, "CMD");
 
-    let mut iter = message.params()?.into_iter();
+    let mut iter = message.params()?;
     assert_eq!(iter.next(), Some("param1"));
     assert_eq!(iter.next(), Some("param2"));
     assert!(iter.next().is_none());
@@ -47,7 +49,7 @@ fn test_parse() -> Result<(), Box<dyn Error>> {
 fn test_tags() -> Result<(), Box<dyn Error>> {
     let message = Message::from("@tag1=value1;tag2=value2 CMD");
 
-    let mut tags = message.tags()?.into_iter();
+    let mut tags = message.tags()?;
     let (key, val) = tags.next().unwrap()?;
     assert_eq!(key, "tag1");
     assert_eq!(val, "value1");
@@ -58,7 +60,7 @@ fn test_tags() -> Result<(), Box<dyn Error>> {
 
     let message = Message::from("@tag1=value1 CMD");
 
-    let mut tags = message.tags()?.into_iter();
+    let mut tags = message.tags()?;
     let (key, val) = tags.next().unwrap()?;
     assert_eq!(key, "tag1");
     assert_eq!(val, "value1");
@@ -66,7 +68,7 @@ fn test_tags() -> Result<(), Box<dyn Error>> {
 
     let message = Message::from("@tag1=value1;tag2=value2 :name CMD :trailing");
 
-    let mut tags = message.tags()?.into_iter();
+    let mut tags = message.tags()?;
     let (key, val) = tags.next().unwrap()?;
     assert_eq!(key, "tag1");
     assert_eq!(val, "value1");
@@ -79,7 +81,7 @@ fn test_tags() -> Result<(), Box<dyn Error>> {
 
     let message = Message::from("@tag1=value1;tag2=value2 CMD :trailing");
 
-    let mut tags = message.tags()?.into_iter();
+    let mut tags = message.tags()?;
     let (key, val) = tags.next().unwrap()?;
     assert_eq!(key, "tag1");
     assert_eq!(val, "value1");
@@ -102,7 +104,7 @@ fn test_without_prefix() -> Result<(), Box<dyn Error>> {
 
     assert_eq!(message.command()?, "CMD");
 
-    let mut iter = message.params()?.into_iter();
+    let mut iter = message.params()?;
     assert_eq!(iter.next(), Some("param1"));
     assert_eq!(iter.next(), Some("param2"));
     assert!(iter.next().is_none());
@@ -164,10 +166,10 @@ fn test_prefix() -> Result<(), Box<dyn Error>> {
 
     let prefix = message.prefix()?;
     assert!(prefix.is_some());
-    let (name, user, host) = prefix.unwrap();
-    assert_eq!(name, "name");
-    assert!(user.is_none());
-    assert!(host.is_none());
+    let prefix = prefix.unwrap();
+    assert_eq!(prefix.name(), "name");
+    assert!(prefix.user().is_none());
+    assert!(prefix.host().is_none());
 
     assert_eq!(message.command()?, "CMD");
 
@@ -178,10 +180,10 @@ fn test_prefix() -> Result<(), Box<dyn Error>> {
 
     let prefix = message.prefix()?;
     assert!(prefix.is_some());
-    let (name, user, host) = prefix.unwrap();
-    assert_eq!(name, "name");
-    assert!(user.is_none());
-    assert_eq!(host, Some("host"));
+    let prefix = prefix.unwrap();
+    assert_eq!(prefix.name(), "name");
+    assert!(prefix.user().is_none());
+    assert_eq!(prefix.host(), Some("host"));
 
     assert_eq!(message.command()?, "CMD");
 
@@ -220,3 +222,49 @@ fn test_message_builder() -> Result<(), ParserError> {
 
     Ok(())
 }
+
+#[test]
+fn test_tag_escaping() -> Result<(), Box<dyn Error>> {
+    let value = "semi;colon space\\backslash\r\n";
+    let message = Message::builder("CMD").tag("key", value).build();
+    assert_eq!(
+        message.to_string(),
+        "@key=semi\\:colon\\sspace\\\\backslash\\r\\n CMD"
+    );
+
+    let parsed = message.parse()?;
+    assert_eq!(Some(value), parsed.tag_unescaped("key").as_deref());
+
+    let (key, unescaped) = message.tags_unescaped()?.next().unwrap()?;
+    assert_eq!(key, "key");
+    assert_eq!(unescaped, value);
+
+    // Builder from an existing message must not escape twice
+    let rebuilt = message.to_builder()?.build();
+    assert_eq!(rebuilt, message);
+
+    Ok(())
+}
+
+#[test]
+fn test_typed_command() -> Result<(), Box<dyn Error>> {
+    let message = Message::from(":nick!user@host Privmsg #channel :Hello");
+    assert_eq!(message.typed_command()?, Command::Privmsg);
+    assert_eq!(message.parse()?.typed_command(), Some(Command::Privmsg));
+
+    let message = Message::from(":irc.example.net 001 nick :Welcome");
+    assert_eq!(
+        message.typed_command()?,
+        Command::Numeric(Numeric::RPL_WELCOME)
+    );
+    assert_eq!(message.numeric()?, Some(Numeric::RPL_WELCOME));
+    assert_eq!(message.parse()?.numeric(), Some(Numeric::RPL_WELCOME));
+
+    let message = Message::builder(Command::Cap)
+        .param("LS")
+        .param("302")
+        .build();
+    assert_eq!(message.to_string(), "CAP LS 302");
+
+    Ok(())
+}
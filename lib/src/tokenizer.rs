@@ -1,7 +1,9 @@
-use crate::errors::ParserError;
+use crate::errors::{Component, ParserError, ParserErrorKind};
+use crate::numeric::Numeric;
 use crate::parsed::Parsed;
 use crate::prefix::Prefix;
-use std::collections::{HashMap, HashSet};
+use crate::tags::Tags;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
@@ -19,12 +21,36 @@ use std::marker::PhantomData;
 #[derive(Eq, PartialEq, Debug, Copy, Clone)]
 pub struct Tokenizer<'a, T: State> {
     raw: &'a str,
+    /// The whole message to compute offsets of errors.
+    origin: &'a str,
     state: PhantomData<T>,
 }
 
 pub trait State: PartialEq + Eq + Debug {}
 
 impl<'a, S: State> Tokenizer<'a, S> {
+    /// Returns the offset of the remaining message in the whole message.
+    fn offset(&self) -> usize {
+        self.raw.as_ptr() as usize - self.origin.as_ptr() as usize
+    }
+
+    /// Creates an error for the region starting at `start` in the remaining message.
+    fn error(
+        &self,
+        kind: ParserErrorKind,
+        component: Component,
+        start: usize,
+        length: usize,
+    ) -> ParserError {
+        ParserError::new(kind, component, self.offset() + start, length)
+    }
+
+    /// Creates an error for a command missing at the end of the message.
+    fn no_command(&self) -> ParserError {
+        let end = self.raw.len();
+        self.error(ParserErrorKind::NoCommand, Component::Command, end, 0)
+    }
+
     fn skip_until_char(&mut self, ch: char, skip_char: bool) {
         if self.raw.starts_with(ch) {
             return;
@@ -34,7 +60,7 @@ impl<'a, S: State> Tokenizer<'a, S> {
             .raw
             .find(ch)
             .map(|space_pos| if skip_char { space_pos + 1 } else { space_pos })
-            .unwrap_or_else(|| self.raw.len());
+            .unwrap_or(self.raw.len());
         self.raw = &self.raw[end..];
     }
 
@@ -43,7 +69,7 @@ impl<'a, S: State> Tokenizer<'a, S> {
             return;
         }
 
-        let end = self.raw.find(s).unwrap_or_else(|| self.raw.len());
+        let end = self.raw.find(s).unwrap_or(self.raw.len());
         self.raw = &self.raw[end..];
     }
 
@@ -84,17 +110,23 @@ impl State for Start {}
 impl<'a> Tokenizer<'a, Start> {
     pub fn new(raw: &'a str) -> Result<Self, ParserError> {
         if raw.is_empty() {
-            Err(ParserError::NoCommand)
+            Err(ParserError::new(
+                ParserErrorKind::NoCommand,
+                Component::Command,
+                0,
+                0,
+            ))
         } else {
             Ok(Tokenizer {
                 raw,
-                state: PhantomData::default(),
+                origin: raw,
+                state: PhantomData,
             })
         }
     }
 
     pub fn parse_partial(self, mut cfg: PartialCfg<'a>) -> Result<Parsed<'a>, ParserError> {
-        let mut result_tags = HashMap::new();
+        let mut result_tags = Tags::new();
         let mut result_prefix = None;
         let mut result_command = None;
         let mut result_params = Vec::new();
@@ -103,7 +135,7 @@ impl<'a> Tokenizer<'a, Start> {
         // Parse tags
         let mut tokenizer = self.tags();
         if !cfg.tags.is_empty() {
-            let mut tags = HashMap::with_capacity(cfg.tags.len());
+            let mut tags = Tags::with_capacity(cfg.tags.len());
             let mut iter = tokenizer.as_iter();
             while !cfg.tags.is_empty() {
                 match iter.next() {
@@ -122,10 +154,15 @@ impl<'a> Tokenizer<'a, Start> {
         // Parse prefix
         let mut tokenizer = tokenizer.prefix();
         if let Some((user, host)) = cfg.prefix {
-            result_prefix = Some((
-                tokenizer.name()?.ok_or(ParserError::PrefixWithoutName)?,
-                if user { tokenizer.user()? } else { None },
-                if host { tokenizer.name()? } else { None },
+            let missing_name =
+                tokenizer.error(ParserErrorKind::PrefixWithoutName, Component::Prefix, 0, 0);
+            let name = tokenizer.name()?.ok_or(missing_name)?;
+            // The user has to be consumed to reach the host
+            let prefix_user = tokenizer.user()?;
+            result_prefix = Some(Prefix::new(
+                name,
+                if user { prefix_user } else { None },
+                if host { tokenizer.host()? } else { None },
             ));
         }
 
@@ -171,7 +208,8 @@ impl<'a> Tokenizer<'a, Start> {
     pub fn tags(self) -> Tokenizer<'a, TagsState> {
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -179,7 +217,8 @@ impl<'a> Tokenizer<'a, Start> {
         self.skip_tags();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -187,7 +226,8 @@ impl<'a> Tokenizer<'a, Start> {
         self.skip_prefix();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -195,7 +235,8 @@ impl<'a> Tokenizer<'a, Start> {
         self.skip_command();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -203,7 +244,8 @@ impl<'a> Tokenizer<'a, Start> {
         self.skip_params();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 }
@@ -215,14 +257,21 @@ impl State for TagsState {}
 
 impl<'a> Tokenizer<'a, TagsState> {
     pub fn as_iter(&mut self) -> IntoTagsIter<'a> {
-        IntoTagsIter(*self)
+        IntoTagsIter(IntoOptionalTagsIter(*self))
+    }
+
+    /// Like [Tokenizer::as_iter] but returns `None` as value of tags without value like
+    /// `@key` in contrast to the empty value of `@key=`.
+    pub fn as_optional_iter(&mut self) -> IntoOptionalTagsIter<'a> {
+        IntoOptionalTagsIter(*self)
     }
 
     pub fn prefix(mut self) -> Tokenizer<'a, PrefixState> {
         self.skip_tags();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -230,7 +279,8 @@ impl<'a> Tokenizer<'a, TagsState> {
         self.skip_prefix();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -238,7 +288,8 @@ impl<'a> Tokenizer<'a, TagsState> {
         self.skip_command();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -246,7 +297,8 @@ impl<'a> Tokenizer<'a, TagsState> {
         self.skip_params();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 }
@@ -256,42 +308,76 @@ impl<'a> IntoIterator for Tokenizer<'a, TagsState> {
     type IntoIter = IntoTagsIter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        IntoTagsIter(self)
+        IntoTagsIter(IntoOptionalTagsIter(self))
     }
 }
 
-pub struct IntoTagsIter<'a>(Tokenizer<'a, TagsState>);
+pub struct IntoTagsIter<'a>(IntoOptionalTagsIter<'a>);
 
 impl<'a> Iterator for IntoTagsIter<'a> {
     type Item = Result<(&'a str, &'a str), ParserError>;
 
+    fn next(&mut self) -> Option<Self::Item> {
+        // Tags without value get an empty value directly after the key
+        self.0
+            .next()
+            .map(|res| res.map(|(key, value)| (key, value.unwrap_or(&key[key.len()..]))))
+    }
+}
+
+pub struct IntoOptionalTagsIter<'a>(Tokenizer<'a, TagsState>);
+
+impl<'a> Iterator for IntoOptionalTagsIter<'a> {
+    type Item = Result<(&'a str, Option<&'a str>), ParserError>;
+
     fn next(&mut self) -> Option<Self::Item> {
         match &self.0.raw[..1] {
             "@" | ";" => {
                 let key_start = 1;
+                // Tags without value end at the next ';' or ' '
                 let key_end = self.0.raw[key_start..]
-                    .find(&['='][..])
-                    .map(|key_end| (key_end + key_start, key_end + key_start + 1))
-                    .or_else(|| {
-                        let key_end = key_start + self.0.raw[key_start..].find(&[' ', ';'][..])?;
-                        Some((key_end, key_end))
+                    .find(&['=', ';', ' '][..])
+                    .map(|key_end| key_start + key_end)
+                    .map(|key_end| match &self.0.raw[key_end..=key_end] {
+                        "=" => (key_end, Some(key_end + 1)),
+                        _ => (key_end, None),
                     });
                 if key_end.is_none() {
+                    let why = self.0.error(
+                        ParserErrorKind::NoTagKeyEnd,
+                        Component::Tags,
+                        key_start,
+                        self.0.raw.len() - key_start,
+                    );
                     // Skip till the end as only tags seem to be present
                     self.0.skip_to_end();
-                    return Some(Err(ParserError::NoTagKeyEnd));
+                    return Some(Err(why));
                 }
                 let (key_end, val_start) = key_end.unwrap();
+                let val_start = match val_start {
+                    Some(val_start) => val_start,
+                    None => {
+                        let key = &self.0.raw[key_start..key_end];
+                        self.0.raw = &self.0.raw[key_end..];
+                        return Some(Ok((key, None)));
+                    }
+                };
                 let val_end = self.0.raw[val_start..].find(&[';', ' '][..]);
                 if val_end.is_none() {
+                    let why = self.0.error(
+                        ParserErrorKind::NoTagValueEnd,
+                        Component::Tags,
+                        val_start,
+                        self.0.raw.len() - val_start,
+                    );
                     // Skip till the end as only tags seem to be present
                     self.0.skip_to_end();
-                    return Some(Err(ParserError::NoTagValueEnd));
+                    return Some(Err(why));
                 }
                 let val_end = val_start + val_end.unwrap();
                 let key_val = (
                     &self.0.raw[key_start..key_end],
-                    &self.0.raw[val_start..val_end],
+                    Some(&self.0.raw[val_start..val_end]),
                 );
                 self.0.raw = &self.0.raw[val_end..];
                 Some(Ok(key_val))
@@ -316,7 +402,7 @@ impl<'a> Tokenizer<'a, PrefixState> {
             let end = self
                 .raw
                 .find(&['!', '@', ' '][..])
-                .ok_or(ParserError::NoCommand)?;
+                .ok_or_else(|| self.no_command())?;
             let split = self.raw.split_at(end);
             name = Some(&split.0[1..]);
             self.raw = split.1;
@@ -330,7 +416,7 @@ impl<'a> Tokenizer<'a, PrefixState> {
             let end = self
                 .raw
                 .find(&['@', ' '][..])
-                .ok_or(ParserError::NoCommand)?;
+                .ok_or_else(|| self.no_command())?;
             let split = self.raw.split_at(end);
             user = Some(&split.0[1..]);
             self.raw = split.1;
@@ -341,7 +427,7 @@ impl<'a> Tokenizer<'a, PrefixState> {
     pub fn host(&mut self) -> Result<Option<&'a str>, ParserError> {
         let mut host = None;
         if self.raw.starts_with('@') {
-            let end = self.raw.find(' ').ok_or(ParserError::NoCommand)?;
+            let end = self.raw.find(' ').ok_or_else(|| self.no_command())?;
             let split = self.raw.split_at(end);
             host = Some(&split.0[1..]);
             self.raw = split.1;
@@ -357,11 +443,17 @@ impl<'a> Tokenizer<'a, PrefixState> {
         if !self.raw.starts_with(':') {
             return Ok(None);
         }
+        let start = self.offset();
         let (name, user, host) = (self.name()?, self.user()?, self.host()?);
         if name.is_none() && (user.is_some() || host.is_some()) {
-            Err(ParserError::PrefixWithoutName)
+            Err(ParserError::new(
+                ParserErrorKind::PrefixWithoutName,
+                Component::Prefix,
+                start,
+                self.offset() - start,
+            ))
         } else {
-            Ok(Some((name.unwrap(), user, host)))
+            Ok(Some(Prefix::new(name.unwrap(), user, host)))
         }
     }
 
@@ -369,7 +461,8 @@ impl<'a> Tokenizer<'a, PrefixState> {
         self.skip_prefix();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -377,7 +470,8 @@ impl<'a> Tokenizer<'a, PrefixState> {
         self.skip_command();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -385,7 +479,8 @@ impl<'a> Tokenizer<'a, PrefixState> {
         self.skip_params();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 }
@@ -401,20 +496,26 @@ impl<'a> Tokenizer<'a, CommandState> {
             self.raw = &self.raw[1..];
         }
 
-        let end = self.raw.find(' ').unwrap_or_else(|| self.raw.len());
+        let end = self.raw.find(' ').unwrap_or(self.raw.len());
         let (command, rest) = self.raw.split_at(end);
         if command.is_empty() {
-            return Err(ParserError::NoCommand);
+            return Err(self.error(ParserErrorKind::NoCommand, Component::Command, 0, 0));
         }
         self.raw = rest;
         Ok(command)
     }
 
+    /// Parses the command as numeric. Returns [None] if the command isn't a three digit numeric.
+    pub fn numeric(&mut self) -> Result<Option<Numeric>, ParserError> {
+        self.command().map(Numeric::parse)
+    }
+
     pub fn params(mut self) -> Tokenizer<'a, ParamsState> {
         self.skip_command();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -422,7 +523,8 @@ impl<'a> Tokenizer<'a, CommandState> {
         self.skip_params();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 }
@@ -437,7 +539,8 @@ impl<'a> Tokenizer<'a, ParamsState> {
         self.skip_params();
         Tokenizer {
             raw: self.raw,
-            state: PhantomData::default(),
+            origin: self.origin,
+            state: PhantomData,
         }
     }
 
@@ -470,7 +573,7 @@ impl<'a> Iterator for IntoParamsIter<'a> {
             .raw
             .find(' ')
             .or_else(|| self.0.raw.find(" :"))
-            .unwrap_or_else(|| self.0.raw.len());
+            .unwrap_or(self.0.raw.len());
         let (param, rest) = self.0.raw.split_at(end);
         self.0.raw = rest;
         Some(param)
@@ -515,12 +618,61 @@ impl<'a> Default for PartialCfg<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::tokenizer::{ParserError, Tokenizer};
+    use crate::errors::{Component, ParserError, ParserErrorKind};
+    use crate::prefix::Prefix;
+    use crate::tokenizer::{PartialCfg, Tokenizer};
     use std::error::Error;
 
     #[test]
     fn test_empty() {
-        assert_eq!(Err(ParserError::NoCommand), Tokenizer::new(""));
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::NoCommand,
+                Component::Command,
+                0,
+                0
+            )),
+            Tokenizer::new("")
+        );
+    }
+
+    #[test]
+    fn test_error_offsets() -> Result<(), Box<dyn Error>> {
+        let mut tokenizer = Tokenizer::new("@key1=value1;key2")?.tags();
+        let mut iter = tokenizer.as_iter();
+        assert_eq!(Some(Ok(("key1", "value1"))), iter.next());
+        assert_eq!(
+            Some(Err(ParserError::new(
+                ParserErrorKind::NoTagKeyEnd,
+                Component::Tags,
+                13,
+                4
+            ))),
+            iter.next()
+        );
+
+        let mut tokenizer = Tokenizer::new("@key=value :name!user")?.prefix();
+        assert_eq!(
+            Err(ParserError::new(
+                ParserErrorKind::NoCommand,
+                Component::Command,
+                21,
+                0
+            )),
+            tokenizer.parts()
+        );
+
+        let mut tokenizer = Tokenizer::new("@key=value :name ")?.command();
+        let why = tokenizer.command().unwrap_err();
+        assert_eq!(
+            ParserError::new(ParserErrorKind::NoCommand, Component::Command, 17, 0),
+            why
+        );
+        assert_eq!(
+            "Missing command in message in command at byte 17\n@key=value :name \n                 ^",
+            why.render("@key=value :name ").to_string()
+        );
+        Ok(())
     }
 
     #[test]
@@ -575,6 +727,21 @@ mod tests {
         assert_eq!(None, iter.next());
         assert_eq!(None, tokenizer.trailing().trailing());
 
+        // Tags without value are distinguished from empty values
+        let mut tokenizer = Tokenizer::new("@key1=value1;key2=;key3 CMD")?.tags();
+        let mut iter = tokenizer.as_optional_iter();
+        assert_eq!(Some(Ok(("key1", Some("value1")))), iter.next());
+        assert_eq!(Some(Ok(("key2", Some("")))), iter.next());
+        assert_eq!(Some(Ok(("key3", None))), iter.next());
+        assert_eq!(None, iter.next());
+
+        // Tags without value followed by tags with value
+        let mut tokenizer = Tokenizer::new("@key1;key2=value2 CMD")?.tags();
+        let mut iter = tokenizer.as_iter();
+        assert_eq!(Some(Ok(("key1", ""))), iter.next());
+        assert_eq!(Some(Ok(("key2", "value2"))), iter.next());
+        assert_eq!(None, iter.next());
+
         Ok(())
     }
 
@@ -585,7 +752,7 @@ mod tests {
         assert_eq!(None, iter.next());
         let mut tokenizer = tokenizer.prefix();
         assert_eq!(
-            Some(("name", Some("user"), Some("host"))),
+            Some(Prefix::new("name", Some("user"), Some("host"))),
             tokenizer.parts()?
         );
         let mut tokenizer = tokenizer.command();
@@ -604,7 +771,7 @@ mod tests {
         let mut iter = tokenizer.as_iter();
         assert_eq!(None, iter.next());
         let mut tokenizer = tokenizer.prefix();
-        assert_eq!(Some(("name", None, None)), tokenizer.parts()?);
+        assert_eq!(Some(Prefix::new("name", None, None)), tokenizer.parts()?);
         let mut tokenizer = tokenizer.command();
         assert_eq!("CMD", tokenizer.command()?);
         let mut tokenizer = tokenizer.params();
@@ -621,7 +788,10 @@ mod tests {
         let mut iter = tokenizer.as_iter();
         assert_eq!(None, iter.next());
         let mut tokenizer = tokenizer.prefix();
-        assert_eq!(Some(("name", Some("user"), None)), tokenizer.parts()?);
+        assert_eq!(
+            Some(Prefix::new("name", Some("user"), None)),
+            tokenizer.parts()?
+        );
         let mut tokenizer = tokenizer.command();
         assert_eq!("CMD", tokenizer.command()?);
         let mut tokenizer = tokenizer.params();
@@ -638,7 +808,10 @@ mod tests {
         let mut iter = tokenizer.as_iter();
         assert_eq!(None, iter.next());
         let mut tokenizer = tokenizer.prefix();
-        assert_eq!(Some(("name", None, Some("host"))), tokenizer.parts()?);
+        assert_eq!(
+            Some(Prefix::new("name", None, Some("host"))),
+            tokenizer.parts()?
+        );
         let mut tokenizer = tokenizer.command();
         assert_eq!("CMD", tokenizer.command()?);
         let mut tokenizer = tokenizer.params();
@@ -649,6 +822,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_partial_prefix() -> Result<(), Box<dyn Error>> {
+        for (user, host, expected) in &[
+            (false, true, Prefix::new("name", None, Some("host"))),
+            (true, false, Prefix::new("name", Some("user"), None)),
+            (false, false, Prefix::new("name", None, None)),
+        ] {
+            let parsed =
+                Tokenizer::new(":name!user@host CMD param")?.parse_partial(PartialCfg {
+                    prefix: Some((*user, *host)),
+                    params: vec![0],
+                    ..PartialCfg::default()
+                })?;
+            assert_eq!(Some(expected), parsed.prefix());
+            assert_eq!(Some("CMD"), parsed.command());
+            assert_eq!(Some("param"), parsed.param(0));
+        }
+
+        let parsed = Tokenizer::new(":name@host CMD")?.parse_partial(PartialCfg {
+            prefix: Some((false, true)),
+            ..PartialCfg::default()
+        })?;
+        assert_eq!(
+            Some(&Prefix::new("name", None, Some("host"))),
+            parsed.prefix()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_params() -> Result<(), Box<dyn Error>> {
         let mut tokenizer = Tokenizer::new("CMD param0 param1")?.tags();
@@ -715,7 +917,7 @@ mod tests {
         assert_eq!(Some(Ok(("key2", "value2"))), iter.next());
         let mut tokenizer = tokenizer.prefix();
         assert_eq!(
-            Some(("name", Some("user"), Some("host"))),
+            Some(Prefix::new("name", Some("user"), Some("host"))),
             tokenizer.parts()?
         );
         let mut tokenizer = tokenizer.command();
@@ -0,0 +1,588 @@
+//! Zero-copy typed views on [Parsed] messages for commonly used commands.
+//!
+//! Every view is created through [TryFrom] from a [Parsed] message and can be converted
+//! back into a [Builder]. The trailing parameter is treated as the last positional parameter,
+//! so `PRIVMSG #channel text` and `PRIVMSG #channel :text` result in the same view.
+//!
+//! # Usage
+//!
+//! ```rust
+//! use irc_rust::Message;
+//! use irc_rust::builder::Builder;
+//! use irc_rust::typed::Kick;
+//! use std::convert::TryFrom;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let message = Message::from(":op!user@host KICK #channel nick1,nick2 :Bye");
+//! let parsed = message.parse()?;
+//! let kick = Kick::try_from(&parsed)?;
+//! assert_eq!("#channel", kick.channel);
+//! assert_eq!(vec!["nick1", "nick2"], kick.users);
+//! assert_eq!(Some("Bye"), kick.reason);
+//!
+//! let message = Builder::from(kick).build();
+//! assert_eq!("KICK #channel nick1,nick2 :Bye", message.to_string());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::builder::{self, Builder};
+use crate::command::Command;
+use crate::errors::ConversionError;
+use crate::parsed::Parsed;
+use std::convert::TryFrom;
+
+/// Positional parameters of a message including the trailing parameter.
+struct Params<'a> {
+    command: &'static str,
+    params: Vec<Option<&'a str>>,
+}
+
+impl<'a> Params<'a> {
+    fn new(
+        parsed: &Parsed<'a>,
+        expected: Command<'static>,
+        command: &'static str,
+    ) -> Result<Self, ConversionError> {
+        let found = parsed.typed_command().ok_or(ConversionError::NoCommand)?;
+        if found != expected {
+            return Err(ConversionError::WrongCommand {
+                expected: command,
+                found: found.to_string(),
+            });
+        }
+        let mut params = parsed.params().copied().collect::<Vec<_>>();
+        if let Some(trailing) = parsed.trailing() {
+            params.push(Some(trailing));
+        }
+        Ok(Params { command, params })
+    }
+
+    fn required(&self, index: usize, name: &'static str) -> Result<&'a str, ConversionError> {
+        self.optional(index).ok_or(ConversionError::MissingParam {
+            command: self.command,
+            index,
+            name,
+        })
+    }
+
+    fn optional(&self, index: usize) -> Option<&'a str> {
+        self.params.get(index).copied().flatten()
+    }
+
+    fn rest(&self, from: usize) -> Vec<&'a str> {
+        self.params.iter().skip(from).flatten().copied().collect()
+    }
+
+    /// Returns the non-empty items of a required comma separated list. Fails if there are none.
+    fn list(&self, index: usize, name: &'static str) -> Result<Vec<&'a str>, ConversionError> {
+        let items = self
+            .required(index, name)?
+            .split(',')
+            .filter(|item| !item.is_empty())
+            .collect::<Vec<_>>();
+        if items.is_empty() {
+            return Err(ConversionError::MissingParam {
+                command: self.command,
+                index,
+                name,
+            });
+        }
+        Ok(items)
+    }
+}
+
+fn push_list(builder: Builder, items: &[&str]) -> Builder {
+    let list = items.join(",");
+    if list.is_empty() {
+        builder
+    } else {
+        builder.param(list)
+    }
+}
+
+/// Adds the last param of a message, as trailing if it can't be sent as a middle param.
+fn push_last(builder: Builder, param: &str) -> Builder {
+    if builder::needs_trailing(param) {
+        builder.trailing(param)
+    } else {
+        builder.param(param)
+    }
+}
+
+/// `PRIVMSG <target>{,<target>} <text>`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Privmsg<'a> {
+    pub targets: Vec<&'a str>,
+    pub text: &'a str,
+}
+
+impl<'a, 'p> TryFrom<&'p Parsed<'a>> for Privmsg<'a> {
+    type Error = ConversionError;
+
+    fn try_from(parsed: &'p Parsed<'a>) -> Result<Self, Self::Error> {
+        let params = Params::new(parsed, Command::Privmsg, "PRIVMSG")?;
+        Ok(Privmsg {
+            targets: params.list(0, "targets")?,
+            text: params.required(1, "text")?,
+        })
+    }
+}
+
+impl<'a> From<Privmsg<'a>> for Builder {
+    fn from(privmsg: Privmsg<'a>) -> Self {
+        push_list(Builder::new(Command::Privmsg), &privmsg.targets).trailing(privmsg.text)
+    }
+}
+
+/// `NOTICE <target>{,<target>} <text>`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Notice<'a> {
+    pub targets: Vec<&'a str>,
+    pub text: &'a str,
+}
+
+impl<'a, 'p> TryFrom<&'p Parsed<'a>> for Notice<'a> {
+    type Error = ConversionError;
+
+    fn try_from(parsed: &'p Parsed<'a>) -> Result<Self, Self::Error> {
+        let params = Params::new(parsed, Command::Notice, "NOTICE")?;
+        Ok(Notice {
+            targets: params.list(0, "targets")?,
+            text: params.required(1, "text")?,
+        })
+    }
+}
+
+impl<'a> From<Notice<'a>> for Builder {
+    fn from(notice: Notice<'a>) -> Self {
+        push_list(Builder::new(Command::Notice), &notice.targets).trailing(notice.text)
+    }
+}
+
+/// `JOIN <channel>{,<channel>} [<key>{,<key>}]`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Join<'a> {
+    pub channels: Vec<&'a str>,
+    /// Keys are paired with the channels by position, so channels without a key have an
+    /// empty entry if a later channel has one.
+    pub keys: Vec<&'a str>,
+}
+
+impl<'a, 'p> TryFrom<&'p Parsed<'a>> for Join<'a> {
+    type Error = ConversionError;
+
+    fn try_from(parsed: &'p Parsed<'a>) -> Result<Self, Self::Error> {
+        let params = Params::new(parsed, Command::Join, "JOIN")?;
+        Ok(Join {
+            channels: params.list(0, "channels")?,
+            keys: params
+                .optional(1)
+                .map(|keys| keys.split(',').collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl<'a> From<Join<'a>> for Builder {
+    fn from(join: Join<'a>) -> Self {
+        let keys = join.keys.join(",");
+        if keys.is_empty() {
+            push_last(Builder::new(Command::Join), &join.channels.join(","))
+        } else {
+            let builder = push_list(Builder::new(Command::Join), &join.channels);
+            push_last(builder, &keys)
+        }
+    }
+}
+
+/// `PART <channel>{,<channel>} [<reason>]`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Part<'a> {
+    pub channels: Vec<&'a str>,
+    pub reason: Option<&'a str>,
+}
+
+impl<'a, 'p> TryFrom<&'p Parsed<'a>> for Part<'a> {
+    type Error = ConversionError;
+
+    fn try_from(parsed: &'p Parsed<'a>) -> Result<Self, Self::Error> {
+        let params = Params::new(parsed, Command::Part, "PART")?;
+        Ok(Part {
+            channels: params.list(0, "channels")?,
+            reason: params.optional(1),
+        })
+    }
+}
+
+impl<'a> From<Part<'a>> for Builder {
+    fn from(part: Part<'a>) -> Self {
+        match part.reason {
+            Some(reason) => push_list(Builder::new(Command::Part), &part.channels).trailing(reason),
+            None => push_last(Builder::new(Command::Part), &part.channels.join(",")),
+        }
+    }
+}
+
+/// `KICK <channel> <user>{,<user>} [<reason>]`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Kick<'a> {
+    pub channel: &'a str,
+    pub users: Vec<&'a str>,
+    pub reason: Option<&'a str>,
+}
+
+impl<'a, 'p> TryFrom<&'p Parsed<'a>> for Kick<'a> {
+    type Error = ConversionError;
+
+    fn try_from(parsed: &'p Parsed<'a>) -> Result<Self, Self::Error> {
+        let params = Params::new(parsed, Command::Kick, "KICK")?;
+        Ok(Kick {
+            channel: params.required(0, "channel")?,
+            users: params.list(1, "users")?,
+            reason: params.optional(2),
+        })
+    }
+}
+
+impl<'a> From<Kick<'a>> for Builder {
+    fn from(kick: Kick<'a>) -> Self {
+        let builder = Builder::new(Command::Kick).param(kick.channel);
+        match kick.reason {
+            Some(reason) => push_list(builder, &kick.users).trailing(reason),
+            None => push_last(builder, &kick.users.join(",")),
+        }
+    }
+}
+
+/// `MODE <target> [<modestring> [<mode arguments>...]]`
+///
+/// Doesn't pair modes with their arguments as this requires the modes supported by the server.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Mode<'a> {
+    pub target: &'a str,
+    pub modes: Option<&'a str>,
+    pub args: Vec<&'a str>,
+}
+
+impl<'a, 'p> TryFrom<&'p Parsed<'a>> for Mode<'a> {
+    type Error = ConversionError;
+
+    fn try_from(parsed: &'p Parsed<'a>) -> Result<Self, Self::Error> {
+        let params = Params::new(parsed, Command::Mode, "MODE")?;
+        Ok(Mode {
+            target: params.required(0, "target")?,
+            modes: params.optional(1),
+            args: params.rest(2),
+        })
+    }
+}
+
+impl<'a> From<Mode<'a>> for Builder {
+    /// Sends the last parameter as trailing if it is empty, contains a space or starts with
+    /// a `:` like a key of `MODE #channel +k :`.
+    fn from(mode: Mode<'a>) -> Self {
+        let mut params = vec![mode.target];
+        params.extend(mode.modes);
+        params.extend(mode.args);
+        let last = params.pop().unwrap_or_default();
+        let builder = params
+            .into_iter()
+            .fold(Builder::new(Command::Mode), Builder::param);
+        push_last(builder, last)
+    }
+}
+
+/// `NICK <nickname>`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Nick<'a> {
+    pub nickname: &'a str,
+}
+
+impl<'a, 'p> TryFrom<&'p Parsed<'a>> for Nick<'a> {
+    type Error = ConversionError;
+
+    fn try_from(parsed: &'p Parsed<'a>) -> Result<Self, Self::Error> {
+        let params = Params::new(parsed, Command::Nick, "NICK")?;
+        Ok(Nick {
+            nickname: params.required(0, "nickname")?,
+        })
+    }
+}
+
+impl<'a> From<Nick<'a>> for Builder {
+    fn from(nick: Nick<'a>) -> Self {
+        push_last(Builder::new(Command::Nick), nick.nickname)
+    }
+}
+
+/// `QUIT [<reason>]`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Quit<'a> {
+    pub reason: Option<&'a str>,
+}
+
+impl<'a, 'p> TryFrom<&'p Parsed<'a>> for Quit<'a> {
+    type Error = ConversionError;
+
+    fn try_from(parsed: &'p Parsed<'a>) -> Result<Self, Self::Error> {
+        let params = Params::new(parsed, Command::Quit, "QUIT")?;
+        Ok(Quit {
+            reason: params.optional(0),
+        })
+    }
+}
+
+impl<'a> From<Quit<'a>> for Builder {
+    fn from(quit: Quit<'a>) -> Self {
+        let builder = Builder::new(Command::Quit);
+        match quit.reason {
+            Some(reason) => builder.trailing(reason),
+            None => builder,
+        }
+    }
+}
+
+/// `TOPIC <channel> [<topic>]`
+///
+/// A topic of `Some("")` clears the topic of the channel.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Topic<'a> {
+    pub channel: &'a str,
+    pub topic: Option<&'a str>,
+}
+
+impl<'a, 'p> TryFrom<&'p Parsed<'a>> for Topic<'a> {
+    type Error = ConversionError;
+
+    fn try_from(parsed: &'p Parsed<'a>) -> Result<Self, Self::Error> {
+        let params = Params::new(parsed, Command::Topic, "TOPIC")?;
+        Ok(Topic {
+            channel: params.required(0, "channel")?,
+            topic: params.optional(1),
+        })
+    }
+}
+
+impl<'a> From<Topic<'a>> for Builder {
+    fn from(topic: Topic<'a>) -> Self {
+        let builder = Builder::new(Command::Topic);
+        match topic.topic {
+            Some(text) => builder.param(topic.channel).trailing(text),
+            None => push_last(builder, topic.channel),
+        }
+    }
+}
+
+/// `INVITE <nickname> <channel>`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Invite<'a> {
+    pub nickname: &'a str,
+    pub channel: &'a str,
+}
+
+impl<'a, 'p> TryFrom<&'p Parsed<'a>> for Invite<'a> {
+    type Error = ConversionError;
+
+    fn try_from(parsed: &'p Parsed<'a>) -> Result<Self, Self::Error> {
+        let params = Params::new(parsed, Command::Invite, "INVITE")?;
+        Ok(Invite {
+            nickname: params.required(0, "nickname")?,
+            channel: params.required(1, "channel")?,
+        })
+    }
+}
+
+impl<'a> From<Invite<'a>> for Builder {
+    fn from(invite: Invite<'a>) -> Self {
+        let builder = Builder::new(Command::Invite).param(invite.nickname);
+        push_last(builder, invite.channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::Builder;
+    use crate::errors::ConversionError;
+    use crate::typed::{Invite, Join, Kick, Mode, Nick, Notice, Part, Privmsg, Quit, Topic};
+    use crate::Message;
+    use std::convert::TryFrom;
+    use std::error::Error;
+
+    #[test]
+    fn test_privmsg() -> Result<(), Box<dyn Error>> {
+        let message = Message::from(":nick!user@host PRIVMSG #a,#b :Hello World");
+        let parsed = message.parse()?;
+        let privmsg = Privmsg::try_from(&parsed)?;
+        assert_eq!(vec!["#a", "#b"], privmsg.targets);
+        assert_eq!("Hello World", privmsg.text);
+        assert_eq!(
+            "PRIVMSG #a,#b :Hello World",
+            Builder::from(privmsg).build().to_string()
+        );
+
+        let message = Message::from("privmsg nick text");
+        let parsed = message.parse()?;
+        assert_eq!("text", Privmsg::try_from(&parsed)?.text);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<(), Box<dyn Error>> {
+        let message = Message::from("PRIVMSG #channel");
+        let parsed = message.parse()?;
+        assert_eq!(
+            Err(ConversionError::MissingParam {
+                command: "PRIVMSG",
+                index: 1,
+                name: "text"
+            }),
+            Privmsg::try_from(&parsed)
+        );
+
+        let message = Message::from("NOTICE #channel :text");
+        let parsed = message.parse()?;
+        assert_eq!(
+            Err(ConversionError::WrongCommand {
+                expected: "PRIVMSG",
+                found: "NOTICE".to_string()
+            }),
+            Privmsg::try_from(&parsed)
+        );
+
+        let message = Message::from("PART ,");
+        let parsed = message.parse()?;
+        assert_eq!(
+            Err(ConversionError::MissingParam {
+                command: "PART",
+                index: 0,
+                name: "channels"
+            }),
+            Part::try_from(&parsed)
+        );
+
+        let message = Message::from("KICK #c :");
+        let parsed = message.parse()?;
+        assert_eq!(
+            Err(ConversionError::MissingParam {
+                command: "KICK",
+                index: 1,
+                name: "users"
+            }),
+            Kick::try_from(&parsed)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_param_as_trailing() -> Result<(), Box<dyn Error>> {
+        let message = Message::from("NICK :");
+        let parsed = message.parse()?;
+        let nick = Nick::try_from(&parsed)?;
+        assert_eq!("NICK :", Builder::from(nick).build().to_string());
+
+        let message = Message::from("INVITE nick :");
+        let parsed = message.parse()?;
+        let invite = Invite::try_from(&parsed)?;
+        assert_eq!("INVITE nick :", Builder::from(invite).build().to_string());
+
+        let message = Message::from("JOIN #a :k y");
+        let parsed = message.parse()?;
+        let join = Join::try_from(&parsed)?;
+        assert_eq!("JOIN #a :k y", Builder::from(join).build().to_string());
+
+        let message = Message::from("JOIN #a :");
+        let parsed = message.parse()?;
+        let join = Join::try_from(&parsed)?;
+        assert_eq!("JOIN #a", Builder::from(join).build().to_string());
+
+        let message = Message::from("TOPIC :");
+        let parsed = message.parse()?;
+        let topic = Topic::try_from(&parsed)?;
+        assert_eq!("TOPIC :", Builder::from(topic).build().to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn test_notice() -> Result<(), Box<dyn Error>> {
+        let message = Message::from("NOTICE * :*** Looking up your hostname");
+        let parsed = message.parse()?;
+        let notice = Notice::try_from(&parsed)?;
+        assert_eq!(vec!["*"], notice.targets);
+        assert_eq!("*** Looking up your hostname", notice.text);
+        Ok(())
+    }
+
+    #[test]
+    fn test_join_part() -> Result<(), Box<dyn Error>> {
+        let message = Message::from("JOIN #a,#b key");
+        let parsed = message.parse()?;
+        let join = Join::try_from(&parsed)?;
+        assert_eq!(vec!["#a", "#b"], join.channels);
+        assert_eq!(vec!["key"], join.keys);
+        assert_eq!("JOIN #a,#b key", Builder::from(join).build().to_string());
+
+        let message = Message::from("JOIN #a,#b,#c ,k2");
+        let parsed = message.parse()?;
+        let join = Join::try_from(&parsed)?;
+        assert_eq!(vec!["#a", "#b", "#c"], join.channels);
+        assert_eq!(vec!["", "k2"], join.keys);
+        assert_eq!("JOIN #a,#b,#c ,k2", Builder::from(join).build().to_string());
+
+        let message = Message::from("PART #a :Gone");
+        let parsed = message.parse()?;
+        let part = Part::try_from(&parsed)?;
+        assert_eq!(vec!["#a"], part.channels);
+        assert_eq!(Some("Gone"), part.reason);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mode() -> Result<(), Box<dyn Error>> {
+        let message = Message::from("MODE #c +ov-k nick1 nick2 key");
+        let parsed = message.parse()?;
+        let mode = Mode::try_from(&parsed)?;
+        assert_eq!("#c", mode.target);
+        assert_eq!(Some("+ov-k"), mode.modes);
+        assert_eq!(vec!["nick1", "nick2", "key"], mode.args);
+        assert_eq!(
+            "MODE #c +ov-k nick1 nick2 key",
+            Builder::from(mode).build().to_string()
+        );
+
+        for (raw, expected) in &[
+            ("MODE #c +k :", "MODE #c +k :"),
+            ("MODE #c +k :a b", "MODE #c +k :a b"),
+            ("MODE #c +k ::key", "MODE #c +k ::key"),
+            ("MODE #c :+k", "MODE #c +k"),
+        ] {
+            let message = Message::from(*raw);
+            let parsed = message.parse()?;
+            let mode = Mode::try_from(&parsed)?;
+            assert_eq!(*expected, Builder::from(mode).build().to_string());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_others() -> Result<(), Box<dyn Error>> {
+        let message = Message::from(":old NICK new");
+        assert_eq!("new", Nick::try_from(&message.parse()?)?.nickname);
+
+        let message = Message::from("QUIT");
+        assert_eq!(None, Quit::try_from(&message.parse()?)?.reason);
+
+        let message = Message::from("TOPIC #c :");
+        assert_eq!(Some(""), Topic::try_from(&message.parse()?)?.topic);
+
+        let message = Message::from("INVITE nick #c");
+        let parsed = message.parse()?;
+        let invite = Invite::try_from(&parsed)?;
+        assert_eq!(("nick", "#c"), (invite.nickname, invite.channel));
+
+        let message = Message::from("KICK #c");
+        assert!(Kick::try_from(&message.parse()?).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,425 @@
+//! Strict validation of messages against the grammar of
+//! [RFC 2812](https://tools.ietf.org/html/rfc2812#section-2.3.1) and the
+//! [IRCv3 message-tags specification](https://ircv3.net/specs/extensions/message-tags).
+//!
+//! The [Tokenizer](crate::tokenizer::Tokenizer) is lenient and accepts almost anything. Use
+//! [validate] or [Message::validate](crate::Message::validate) to check a message before
+//! trusting it. All violations are reported as [ParserError]s with their byte span in the
+//! message.
+//!
+//! Hosts are validated as hostnames, IP addresses or cloaks like `user/example` which
+//! consist of `/` separated segments of hostname and nickname characters.
+
+use crate::errors::{Component, ParserError, ParserErrorKind};
+use std::net::IpAddr;
+use std::ops::Range;
+
+/// Maximum length of the tags section including the leading `@` and the trailing space.
+const MAX_TAGS_LENGTH: usize = 8191;
+/// Maximum length of the rest of the message excluding the CRLF line ending.
+const MAX_MESSAGE_LENGTH: usize = 510;
+/// Maximum number of params including the trailing param.
+const MAX_PARAMS: usize = 15;
+
+/// Validates a raw message without line ending and returns all violations ordered by
+/// their position.
+///
+/// # Usage
+///
+/// ```rust
+/// use irc_rust::errors::{Component, ParserError, ParserErrorKind};
+/// use irc_rust::validate::validate;
+///
+/// assert_eq!(Ok(()), validate("@key=value :nick!user@host PRIVMSG #channel :Hello"));
+/// assert_eq!(
+///     Err(vec![
+///         ParserError::new(ParserErrorKind::InvalidCommand, Component::Command, 0, 4),
+///         ParserError::new(ParserErrorKind::EmptyParam, Component::Params, 4, 2),
+///     ]),
+///     validate("CMD1  param")
+/// );
+/// ```
+pub fn validate(raw: &str) -> Result<(), Vec<ParserError>> {
+    let mut validator = Validator {
+        raw,
+        errors: Vec::new(),
+    };
+    validator.validate();
+    let mut errors = validator.errors;
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        errors.sort_by_key(|why| (why.offset, why.length));
+        Err(errors)
+    }
+}
+
+struct Validator<'a> {
+    raw: &'a str,
+    errors: Vec<ParserError>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(&mut self, kind: ParserErrorKind, component: Component, span: Range<usize>) {
+        self.errors.push(ParserError::new(
+            kind,
+            component,
+            span.start,
+            span.end - span.start,
+        ));
+    }
+
+    /// Returns the position of the next space starting at `from` or the end of the message.
+    fn next_space(&self, from: usize) -> usize {
+        self.raw[from..]
+            .find(' ')
+            .map(|pos| from + pos)
+            .unwrap_or(self.raw.len())
+    }
+
+    fn validate(&mut self) {
+        let mut pos = 0;
+        if self.raw.starts_with('@') {
+            let end = self.next_space(0);
+            self.validate_tags(1..end);
+            pos = (end + 1).min(self.raw.len());
+        }
+        let message_start = pos;
+        if self.raw[pos..].starts_with(':') {
+            let end = self.next_space(pos);
+            self.validate_prefix(pos + 1..end);
+            pos = (end + 1).min(self.raw.len());
+        }
+        let command_start = pos;
+
+        let end = self.next_space(pos);
+        if end == pos {
+            self.report(ParserErrorKind::NoCommand, Component::Command, pos..pos);
+        } else {
+            let command = &self.raw[pos..end];
+            let letters = command.bytes().all(|byte| byte.is_ascii_alphabetic());
+            let numeric = command.len() == 3 && command.bytes().all(|byte| byte.is_ascii_digit());
+            if !letters && !numeric {
+                self.report(
+                    ParserErrorKind::InvalidCommand,
+                    Component::Command,
+                    pos..end,
+                );
+            }
+        }
+        self.validate_params(end);
+
+        let component_at = |pos: usize| {
+            if pos < message_start {
+                Component::Tags
+            } else if pos < command_start {
+                Component::Prefix
+            } else if pos < end {
+                Component::Command
+            } else {
+                Component::Params
+            }
+        };
+        for (pos, byte) in self.raw.bytes().enumerate() {
+            if byte == b'\0' || byte == b'\r' || byte == b'\n' {
+                self.report(
+                    ParserErrorKind::InvalidCharacter,
+                    component_at(pos),
+                    pos..pos + 1,
+                );
+            }
+        }
+        if self.raw.len() - message_start > MAX_MESSAGE_LENGTH {
+            let start = message_start + MAX_MESSAGE_LENGTH;
+            self.report(
+                ParserErrorKind::LineTooLong,
+                component_at(start),
+                start..self.raw.len(),
+            );
+        }
+    }
+
+    fn validate_tags(&mut self, span: Range<usize>) {
+        // Including '@' and the trailing space
+        if span.end + 1 > MAX_TAGS_LENGTH {
+            self.report(ParserErrorKind::TagsTooLong, Component::Tags, 0..span.end);
+        }
+        let mut start = span.start;
+        for tag in self.raw[span.clone()].split(';') {
+            let key_len = tag.find('=').unwrap_or(tag.len());
+            let key = &tag[..key_len];
+            if key.is_empty() {
+                self.report(
+                    ParserErrorKind::EmptyTagKey,
+                    Component::Tags,
+                    start..start + tag.len(),
+                );
+            } else if !valid_tag_key(key) {
+                self.report(
+                    ParserErrorKind::InvalidTagKey,
+                    Component::Tags,
+                    start..start + key_len,
+                );
+            }
+            start += tag.len() + 1;
+        }
+    }
+
+    fn validate_prefix(&mut self, span: Range<usize>) {
+        let prefix = &self.raw[span.clone()];
+        let name_end = prefix.find(&['!', '@'][..]).unwrap_or(prefix.len());
+        let name = &prefix[..name_end];
+        if !valid_hostname(name) && !valid_nickname(name) {
+            self.report(
+                ParserErrorKind::InvalidPrefixName,
+                Component::Prefix,
+                span.start..span.start + name_end,
+            );
+        }
+
+        let host_start = prefix.find('@').map(|pos| pos + 1);
+        if prefix[name_end..].starts_with('!') {
+            let user_end = host_start.map(|pos| pos - 1).unwrap_or(prefix.len());
+            let user = &prefix[name_end + 1..user_end.max(name_end + 1)];
+            // RFC 2812 requires a host if a user is present
+            if user.is_empty() || host_start.is_none() || !valid_user(user) {
+                self.report(
+                    ParserErrorKind::InvalidPrefixUser,
+                    Component::Prefix,
+                    span.start + name_end + 1..span.start + user_end.max(name_end + 1),
+                );
+            }
+        }
+        if let Some(host_start) = host_start {
+            if !valid_host(&prefix[host_start..]) {
+                self.report(
+                    ParserErrorKind::InvalidPrefixHost,
+                    Component::Prefix,
+                    span.start + host_start..span.end,
+                );
+            }
+        }
+    }
+
+    fn validate_params(&mut self, mut pos: usize) {
+        let mut count = 0;
+        while pos < self.raw.len() {
+            // pos is at the space separating the next param
+            pos += 1;
+            count += 1;
+            if count == MAX_PARAMS + 1 {
+                self.report(
+                    ParserErrorKind::TooManyParams,
+                    Component::Params,
+                    pos..self.raw.len(),
+                );
+            }
+            if self.raw[pos..].starts_with(':') {
+                break;
+            }
+            let end = self.next_space(pos);
+            if end == pos {
+                self.report(
+                    ParserErrorKind::EmptyParam,
+                    Component::Params,
+                    pos - 1..(pos + 1).min(self.raw.len()),
+                );
+            }
+            pos = end;
+        }
+    }
+}
+
+fn valid_tag_key(key: &str) -> bool {
+    let key = key.strip_prefix('+').unwrap_or(key);
+    let (vendor, name) = match key.rfind('/') {
+        Some(pos) => (Some(&key[..pos]), &key[pos + 1..]),
+        None => (None, key),
+    };
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-')
+        && vendor.map(valid_hostname).unwrap_or(true)
+}
+
+fn valid_hostname(host: &str) -> bool {
+    !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-')
+        })
+}
+
+fn valid_host(host: &str) -> bool {
+    valid_hostname(host) || host.parse::<IpAddr>().is_ok() || valid_cloak(host)
+}
+
+fn valid_cloak(host: &str) -> bool {
+    host.contains('/')
+        && host.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment.bytes().all(|byte| {
+                    byte.is_ascii_alphanumeric() || is_special(byte) || b"-.".contains(&byte)
+                })
+        })
+}
+
+fn is_special(byte: u8) -> bool {
+    b"[]\\`_^{|}".contains(&byte)
+}
+
+fn valid_nickname(nickname: &str) -> bool {
+    let mut bytes = nickname.bytes();
+    match bytes.next() {
+        Some(first) if first.is_ascii_alphabetic() || is_special(first) => {
+            bytes.all(|byte| byte.is_ascii_alphanumeric() || is_special(byte) || byte == b'-')
+        }
+        _ => false,
+    }
+}
+
+fn valid_user(user: &str) -> bool {
+    !user.bytes().any(|byte| b"\0\r\n @".contains(&byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::{Component, ParserError, ParserErrorKind};
+    use crate::validate::validate;
+
+    fn kinds(raw: &str) -> Vec<ParserErrorKind> {
+        validate(raw)
+            .err()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|why| why.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(Ok(()), validate("CMD"));
+        assert_eq!(Ok(()), validate("001 nick :Welcome"));
+        assert_eq!(
+            Ok(()),
+            validate("@+example.com/key=v\\:a;id :nick!~user@192.168.0.1 PRIVMSG #c :hi :)")
+        );
+        assert_eq!(Ok(()), validate(":irc.example.net NOTICE * :"));
+        assert_eq!(Ok(()), validate(":nick!user@2001:db8::1 JOIN #c"));
+    }
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(
+            Err(vec![ParserError::new(
+                ParserErrorKind::EmptyTagKey,
+                Component::Tags,
+                5,
+                6
+            )]),
+            validate("@a=b;=value CMD")
+        );
+        assert_eq!(vec![ParserErrorKind::InvalidTagKey], kinds("@ke_y=v CMD"));
+        assert_eq!(
+            vec![ParserErrorKind::InvalidTagKey],
+            kinds("@vendor./key CMD")
+        );
+        let long = format!("@key={} CMD", "a".repeat(8200));
+        assert_eq!(vec![ParserErrorKind::TagsTooLong], kinds(&long));
+    }
+
+    #[test]
+    fn test_prefix() {
+        assert_eq!(
+            vec![ParserErrorKind::InvalidPrefixName],
+            kinds(":-nick CMD")
+        );
+        assert_eq!(
+            vec![ParserErrorKind::InvalidPrefixUser],
+            kinds(":nick!user CMD")
+        );
+        assert_eq!(
+            vec![ParserErrorKind::InvalidPrefixUser],
+            kinds(":nick!@host CMD")
+        );
+        assert!(kinds(":nick!user@user/cloak CMD").is_empty());
+        assert!(kinds(":nick!user@gateway/web/irccloud.com/x-abc CMD").is_empty());
+        assert_eq!(
+            vec![ParserErrorKind::InvalidPrefixHost],
+            kinds(":nick!user@user//cloak CMD")
+        );
+        assert_eq!(
+            vec![ParserErrorKind::InvalidPrefixHost],
+            kinds(":nick!user@host/ CMD")
+        );
+        assert_eq!(
+            vec![
+                ParserErrorKind::InvalidPrefixName,
+                ParserErrorKind::NoCommand
+            ],
+            kinds(":")
+        );
+    }
+
+    #[test]
+    fn test_command() {
+        assert_eq!(vec![ParserErrorKind::InvalidCommand], kinds("1234"));
+        assert_eq!(vec![ParserErrorKind::InvalidCommand], kinds("CMD1 param"));
+        assert_eq!(vec![ParserErrorKind::NoCommand], kinds("@key=value "));
+        assert_eq!(vec![ParserErrorKind::NoCommand], kinds(""));
+    }
+
+    #[test]
+    fn test_params() {
+        assert_eq!(
+            Err(vec![ParserError::new(
+                ParserErrorKind::EmptyParam,
+                Component::Params,
+                3,
+                2
+            )]),
+            validate("CMD  param")
+        );
+        assert_eq!(vec![ParserErrorKind::EmptyParam], kinds("CMD param "));
+        let fifteen = format!("CMD{}", " p".repeat(15));
+        assert_eq!(Ok(()), validate(&fifteen));
+        let sixteen = format!("CMD{} :trailing", " p".repeat(15));
+        assert_eq!(
+            Err(vec![ParserError::new(
+                ParserErrorKind::TooManyParams,
+                Component::Params,
+                34,
+                9
+            )]),
+            validate(&sixteen)
+        );
+    }
+
+    #[test]
+    fn test_forbidden_and_length() {
+        assert_eq!(
+            Err(vec![ParserError::new(
+                ParserErrorKind::InvalidCharacter,
+                Component::Params,
+                9,
+                1
+            )]),
+            validate("CMD :line\nbreak")
+        );
+        let long = format!("@key=value CMD :{}", "a".repeat(600));
+        assert_eq!(
+            Err(vec![ParserError::new(
+                ParserErrorKind::LineTooLong,
+                Component::Params,
+                521,
+                95
+            )]),
+            validate(&long)
+        );
+    }
+}